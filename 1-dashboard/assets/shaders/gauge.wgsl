@@ -0,0 +1,86 @@
+#import bevy_sprite::{
+    mesh2d_vertex_output::VertexOutput,
+}
+
+struct Gauge {
+    colors: array<vec4<f32>, 4>,
+    stops: vec4<f32>,
+    background: vec4<f32>,
+    tick_color: vec4<f32>,
+    value: f32,
+    origin: f32,
+    stop_count: u32,
+    gradient_by_value: u32,
+    radial: u32,
+    start_angle: f32,
+    end_angle: f32,
+    inner_radius: f32,
+    ticks: u32,
+    segments: u32,
+}
+
+@group(2) @binding(0)
+var<uniform> gauge: Gauge;
+
+// Width of a tick mark relative to the gauge, and of the gap between segments relative to a segment
+const TICK_WIDTH: f32 = 0.01;
+const SEGMENT_GAP: f32 = 0.2;
+
+fn gradient(position: f32) -> vec4<f32> {
+    var color = gauge.colors[0];
+    for (var i = 1u; i < gauge.stop_count; i++) {
+        color = mix(
+            color,
+            gauge.colors[i],
+            smoothstep(gauge.stops[i - 1u], gauge.stops[i], position)
+        );
+    }
+    return color;
+}
+
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    // Position along the gauge, from 0 to 1
+    var position = in.uv.x;
+    if gauge.radial != 0u {
+        let offset = vec2(in.uv.x - 0.5, 0.5 - in.uv.y) * 2.0;
+        let radius = length(offset);
+        if radius > 1.0 || radius < gauge.inner_radius {
+            return vec4(0.0);
+        }
+        let angle = atan2(offset.x, offset.y);
+        position = (angle - gauge.start_angle) / (gauge.end_angle - gauge.start_angle);
+        if position < 0.0 || position > 1.0 {
+            return vec4(0.0);
+        }
+    }
+
+    if gauge.ticks > 0u {
+        let tick = position * f32(gauge.ticks);
+        let nearest = round(tick);
+        if nearest > 0.0 && nearest < f32(gauge.ticks)
+            && abs(tick - nearest) / f32(gauge.ticks) < TICK_WIDTH / 2.0 {
+            return gauge.tick_color;
+        }
+    }
+
+    var sample = position;
+    if gauge.segments > 0u {
+        let segment = position * f32(gauge.segments);
+        if abs(fract(segment) - 0.5) > 0.5 - SEGMENT_GAP / 2.0 {
+            return vec4(0.0);
+        }
+        // Segments are lit as a whole, depending on their center
+        sample = (floor(segment) + 0.5) / f32(gauge.segments);
+    }
+
+    let fill_start = min(gauge.origin, gauge.value);
+    let fill_end = max(gauge.origin, gauge.value);
+    if fill_start < fill_end && sample >= fill_start && sample <= fill_end {
+        if gauge.gradient_by_value != 0u {
+            return gradient(gauge.value);
+        }
+        return gradient(sample);
+    }
+    return gauge.background;
+}
@@ -0,0 +1,506 @@
+use std::fs;
+
+use bevy::prelude::*;
+use serde::Serialize;
+
+use crate::{
+    battery::{BATTERY_CAPACITY, BatteryLevel},
+    gear::Gear,
+    input::InputBackend,
+    scenario::ScenarioSystems,
+    speed::{BRAKE_RATE, COASTING_DECAY, MAX_SPEED, Speed, THROTTLE_RATE},
+    theme::Themed,
+    widgets::{Widget, widget_enabled, widget_turned_on},
+};
+
+const SAMPLE_INTERVAL: f32 = 0.25;
+// Speed in km/h above which the car is moving, starting a trip
+const MOVING_SPEED: f32 = 1.0;
+/// Upper bounds of the speed bands in km/h, the last band has no upper bound
+const SPEED_BANDS: [f32; 4] = [30.0, 50.0, 80.0, 110.0];
+// Eco score lost for each harsh event, and for driving all the time in the last speed band
+const HARSH_EVENT_PENALTY: f32 = 5.0;
+const MAX_HARSH_PENALTY: f32 = 50.0;
+const FAST_DRIVING_PENALTY: f32 = 30.0;
+const SUMMARY_SIZE: Vec2 = vec2(420.0, 300.0);
+
+pub fn analytics_plugin(app: &mut App) {
+    app.add_plugins(analytics_simulation_plugin).add_systems(
+        Update,
+        (
+            setup.run_if(widget_turned_on(Widget::TripSummary)),
+            display_summary.run_if(widget_enabled(Widget::TripSummary)),
+        )
+            .chain()
+            .after(sample_trip),
+    );
+}
+
+/// Trips from when the car starts moving until it is parked, summarized in a `TripHistory`
+pub fn analytics_simulation_plugin(app: &mut App) {
+    let args: Vec<String> = std::env::args().collect();
+    if let Some(arg) = args.windows(2).find(|arg| arg[0] == "--trip-report") {
+        app.insert_resource(TripReportPath(arg[1].clone()));
+    }
+    app.init_resource::<TripHistory>()
+        .add_systems(
+            Update,
+            (start_trip, sample_trip.run_if(resource_exists::<Trip>)).chain(),
+        )
+        .add_systems(OnEnter(Gear::Park), end_trip)
+        // The last trip ends with the app
+        .add_systems(
+            Last,
+            end_trip
+                .run_if(on_message::<AppExit>)
+                .after(ScenarioSystems),
+        );
+}
+
+/// Accelerations in km/h/s beyond which a sample counts as a harsh event
+#[derive(Clone, Copy, PartialEq, Debug)]
+struct HarshLimits {
+    acceleration: f32,
+    braking: f32,
+}
+
+impl HarshLimits {
+    /// About 0.35 g and -0.4 g, for the speed of a real car
+    const VEHICLE: Self = Self {
+        acceleration: 12.0,
+        braking: -14.0,
+    };
+    /// The keyboard pedals are much quicker than a car, so the limits stay clear of the throttle
+    /// alone, and of the brake with the coasting losses at top speed
+    const KEYBOARD: Self = Self {
+        acceleration: 1.5 * THROTTLE_RATE,
+        braking: -1.25 * (BRAKE_RATE + COASTING_DECAY * MAX_SPEED),
+    };
+
+    fn of(backend: InputBackend) -> Self {
+        match backend {
+            InputBackend::Keyboard => Self::KEYBOARD,
+            _ => Self::VEHICLE,
+        }
+    }
+}
+
+/// Trip in progress
+#[derive(Resource)]
+pub struct Trip {
+    duration: f32,
+    distance: f32,
+    max_speed: f32,
+    energy_used: f32,
+    /// Seconds spent in each speed band
+    speed_bands: [f32; SPEED_BANDS.len() + 1],
+    harsh_accelerations: u32,
+    harsh_brakings: u32,
+    last_speed: Option<f32>,
+    last_battery: Option<f32>,
+    /// The last sample was already harsh, so a long hard acceleration is counted once
+    accelerating_hard: bool,
+    braking_hard: bool,
+    sample_timer: Timer,
+}
+
+impl Default for Trip {
+    fn default() -> Self {
+        Self {
+            duration: 0.0,
+            distance: 0.0,
+            max_speed: 0.0,
+            energy_used: 0.0,
+            speed_bands: default(),
+            harsh_accelerations: 0,
+            harsh_brakings: 0,
+            last_speed: None,
+            last_battery: None,
+            accelerating_hard: false,
+            braking_hard: false,
+            sample_timer: Timer::from_seconds(SAMPLE_INTERVAL, TimerMode::Repeating),
+        }
+    }
+}
+
+impl Trip {
+    fn sample(&mut self, speed: f32, battery: Option<f32>, limits: HarshLimits) {
+        let speed = speed.abs();
+        self.duration += SAMPLE_INTERVAL;
+        // Simulated like the odometer, one minute per second
+        self.distance += speed / 60.0 * SAMPLE_INTERVAL;
+        self.max_speed = self.max_speed.max(speed);
+        let band = SPEED_BANDS.partition_point(|limit| *limit <= speed);
+        self.speed_bands[band] += SAMPLE_INTERVAL;
+
+        if let Some(last_speed) = self.last_speed {
+            // Per second of the dashboard like the pedals, not per simulated minute
+            let acceleration = (speed - last_speed) / SAMPLE_INTERVAL;
+            let accelerating_hard = acceleration > limits.acceleration;
+            let braking_hard = acceleration < limits.braking;
+            self.harsh_accelerations += (accelerating_hard && !self.accelerating_hard) as u32;
+            self.harsh_brakings += (braking_hard && !self.braking_hard) as u32;
+            self.accelerating_hard = accelerating_hard;
+            self.braking_hard = braking_hard;
+        }
+        self.last_speed = Some(speed);
+
+        // Only what is drawn from the battery, charging doesn't count
+        if let Some(battery) = battery {
+            if let Some(last_battery) = self.last_battery {
+                self.energy_used += (last_battery - battery).max(0.0) / 100.0 * BATTERY_CAPACITY;
+            }
+            self.last_battery = Some(battery);
+        }
+    }
+
+    fn report(&self) -> TripReport {
+        let speed_bands = self
+            .speed_bands
+            .iter()
+            .enumerate()
+            .map(|(index, seconds)| SpeedBandTime {
+                from: index
+                    .checked_sub(1)
+                    .map_or(0.0, |previous| SPEED_BANDS[previous]),
+                to: SPEED_BANDS.get(index).copied(),
+                seconds: *seconds,
+            })
+            .collect();
+
+        let harsh_events = (self.harsh_accelerations + self.harsh_brakings) as f32;
+        let fast_fraction = if self.duration > 0.0 {
+            self.speed_bands[SPEED_BANDS.len()] / self.duration
+        } else {
+            0.0
+        };
+        let eco_score = 100.0
+            - (harsh_events * HARSH_EVENT_PENALTY).min(MAX_HARSH_PENALTY)
+            - fast_fraction * FAST_DRIVING_PENALTY;
+
+        TripReport {
+            duration: self.duration,
+            distance: self.distance,
+            average_speed: if self.duration > 0.0 {
+                self.distance / self.duration * 60.0
+            } else {
+                0.0
+            },
+            max_speed: self.max_speed,
+            energy_used: self.energy_used,
+            speed_bands,
+            harsh_accelerations: self.harsh_accelerations,
+            harsh_brakings: self.harsh_brakings,
+            eco_score: eco_score.round().max(0.0) as u32,
+        }
+    }
+}
+
+#[derive(Serialize, Clone, PartialEq, Debug)]
+pub struct SpeedBandTime {
+    /// Speeds in km/h, without an upper bound for the last band
+    pub from: f32,
+    pub to: Option<f32>,
+    pub seconds: f32,
+}
+
+/// Summary of a trip, exported as JSON
+#[derive(Serialize, Clone, PartialEq, Debug)]
+pub struct TripReport {
+    /// Seconds
+    pub duration: f32,
+    /// km
+    pub distance: f32,
+    /// km/h
+    pub average_speed: f32,
+    pub max_speed: f32,
+    /// kWh drawn from the battery
+    pub energy_used: f32,
+    pub speed_bands: Vec<SpeedBandTime>,
+    pub harsh_accelerations: u32,
+    pub harsh_brakings: u32,
+    /// From 0 to 100, lower with harsh events and fast driving
+    pub eco_score: u32,
+}
+
+/// Reports of the trips finished since the dashboard started
+#[derive(Resource, Default)]
+pub struct TripHistory(pub Vec<TripReport>);
+
+/// File the trip reports are written to, given with `--trip-report <file>`
+#[derive(Resource)]
+struct TripReportPath(String);
+
+fn start_trip(mut commands: Commands, trip: Option<Res<Trip>>, speed: Option<Res<Speed>>) {
+    if trip.is_none() && speed.is_some_and(|speed| speed.0.abs() > MOVING_SPEED) {
+        commands.init_resource::<Trip>();
+    }
+}
+
+fn sample_trip(
+    mut trip: ResMut<Trip>,
+    speed: Option<Res<Speed>>,
+    battery: Option<Res<BatteryLevel>>,
+    backend: Res<InputBackend>,
+    time: Res<Time>,
+) {
+    if trip.sample_timer.tick(time.delta()).just_finished() {
+        trip.sample(
+            speed.map_or(0.0, |speed| speed.0),
+            battery.map(|battery| battery.0),
+            HarshLimits::of(*backend),
+        );
+    }
+}
+
+fn end_trip(
+    mut commands: Commands,
+    trip: Option<Res<Trip>>,
+    mut history: ResMut<TripHistory>,
+    path: Option<Res<TripReportPath>>,
+) {
+    let Some(trip) = trip else {
+        return;
+    };
+    let report = trip.report();
+    info!(
+        "Trip ended: {:.1} km, eco score {}",
+        report.distance, report.eco_score
+    );
+    history.0.push(report);
+    commands.remove_resource::<Trip>();
+
+    // All the trips of the run, so they can be compared
+    if let Some(path) = path {
+        let written = serde_json::to_string_pretty(&history.0)
+            .map_err(|error| error.to_string())
+            .and_then(|json| fs::write(&path.0, json).map_err(|error| error.to_string()));
+        match written {
+            Ok(()) => info!("Trip report written to {}", path.0),
+            Err(error) => error!("Couldn't write trip report {}: {error}", path.0),
+        }
+    }
+}
+
+#[derive(Component)]
+struct TripSummary;
+
+#[derive(Component)]
+struct TripSummaryText;
+
+fn setup(mut commands: Commands) {
+    commands.spawn((
+        Widget::TripSummary,
+        // Above the other widgets, like an overlay
+        Transform::from_xyz(0.0, 0.0, 10.0),
+        children![(
+            Sprite::from_color(Color::BLACK.with_alpha(0.9), SUMMARY_SIZE),
+            Visibility::Hidden,
+            TripSummary,
+            children![(
+                Text2d::new(""),
+                TextFont::from_font_size(18.0),
+                Transform::from_xyz(0.0, 0.0, 0.1),
+                Themed::Text,
+                TripSummaryText,
+            )],
+        )],
+    ));
+}
+
+// Shown once parked, until the car moves again
+fn display_summary(
+    trip: Option<Res<Trip>>,
+    history: Res<TripHistory>,
+    mut summary: Single<&mut Visibility, With<TripSummary>>,
+    mut text: Single<&mut Text2d, With<TripSummaryText>>,
+) {
+    let report = history.0.last().filter(|_| trip.is_none());
+    **summary = if report.is_some() {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    };
+    let Some(report) = report else {
+        return;
+    };
+    if !history.is_changed() {
+        return;
+    }
+
+    let bands = report
+        .speed_bands
+        .iter()
+        .map(|band| {
+            let label = match band.to {
+                Some(to) => format!("{:.0}-{to:.0}", band.from),
+                None => format!("{:.0}+", band.from),
+            };
+            format!("{label:>7} km/h {:>5.0} s", band.seconds)
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    text.0 = format!(
+        "Trip summary\n\nEco score {}\n{:.1} km in {:.0} s, {:.0} km/h average\n{:.1} kWh used\n{} harsh accelerations, {} harsh brakings\n\n{bands}",
+        report.eco_score,
+        report.distance,
+        report.duration,
+        report.average_speed,
+        report.energy_used,
+        report.harsh_accelerations,
+        report.harsh_brakings,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        battery::BatteryStatus,
+        speed::speed_simulation_plugin,
+        testing::{hold, release, run_for, set_state, test_app},
+    };
+
+    fn analytics_app() -> App {
+        let mut app = test_app();
+        app.init_state::<Gear>()
+            .add_plugins(analytics_simulation_plugin)
+            .insert_resource(Speed(0.0))
+            .insert_resource(BatteryLevel(80.0));
+        app
+    }
+
+    fn set_speed(app: &mut App, speed: f32) {
+        app.insert_resource(Speed(speed));
+    }
+
+    /// Set the speeds one sample after the other
+    fn drive(app: &mut App, speeds: impl IntoIterator<Item = f32>) {
+        for speed in speeds {
+            set_speed(app, speed);
+            run_for(app, SAMPLE_INTERVAL);
+        }
+    }
+
+    fn park(app: &mut App) -> TripReport {
+        set_state(app, Gear::Park);
+        app.world()
+            .resource::<TripHistory>()
+            .0
+            .last()
+            .unwrap()
+            .clone()
+    }
+
+    #[test]
+    fn trip_starts_when_moving_and_ends_when_parked() {
+        let mut app = analytics_app();
+        run_for(&mut app, 1.0);
+        assert!(!app.world().contains_resource::<Trip>());
+
+        set_speed(&mut app, 60.0);
+        run_for(&mut app, 1.0);
+        assert!(app.world().contains_resource::<Trip>());
+
+        set_speed(&mut app, 0.0);
+        let report = park(&mut app);
+        assert!(!app.world().contains_resource::<Trip>());
+        assert!((report.distance - 1.0).abs() < 0.3);
+        assert_eq!(report.max_speed, 60.0);
+    }
+
+    #[test]
+    fn time_in_speed_bands() {
+        let mut app = analytics_app();
+        set_speed(&mut app, 40.0);
+        run_for(&mut app, 2.0);
+        set_speed(&mut app, 120.0);
+        run_for(&mut app, 1.0);
+        let report = park(&mut app);
+
+        let seconds: Vec<f32> = report.speed_bands.iter().map(|band| band.seconds).collect();
+        assert!((seconds[1] - 2.0).abs() <= SAMPLE_INTERVAL);
+        assert!((seconds[4] - 1.0).abs() <= SAMPLE_INTERVAL);
+        assert_eq!(seconds[0] + seconds[2] + seconds[3], 0.0);
+        assert_eq!(report.speed_bands[4].from, 110.0);
+        assert_eq!(report.speed_bands[4].to, None);
+    }
+
+    #[test]
+    fn smooth_driving_scores_best() {
+        let mut app = analytics_app();
+        for speed in 1..=50 {
+            set_speed(&mut app, speed as f32);
+            run_for(&mut app, 0.25);
+        }
+        let report = park(&mut app);
+        assert_eq!(report.harsh_accelerations + report.harsh_brakings, 0);
+        assert_eq!(report.eco_score, 100);
+    }
+
+    #[test]
+    fn harsh_events_are_counted_once() {
+        let mut app = analytics_app();
+        app.insert_resource(InputBackend::Can);
+        for _ in 0..2 {
+            // Flooring it at 0.45 g, then an emergency stop at 0.7 g, as read from a car
+            drive(&mut app, (1..=15).map(|step| step as f32 * 4.0));
+            drive(&mut app, (0..10).rev().map(|step| step as f32 * 6.0));
+            run_for(&mut app, 1.0);
+        }
+        let report = park(&mut app);
+        assert_eq!(report.harsh_accelerations, 2);
+        assert_eq!(report.harsh_brakings, 2);
+        assert_eq!(report.eco_score, 80);
+    }
+
+    #[test]
+    fn firm_vehicle_driving_is_not_harsh() {
+        let mut app = analytics_app();
+        app.insert_resource(InputBackend::Can);
+        // 0.25 g up to 90 km/h and 0.35 g down to a stop
+        drive(&mut app, (1..=40).map(|step| step as f32 * 2.25));
+        drive(&mut app, (0..30).rev().map(|step| step as f32 * 3.0));
+        let report = park(&mut app);
+        assert_eq!(report.harsh_accelerations, 0);
+        assert_eq!(report.harsh_brakings, 0);
+    }
+
+    #[test]
+    fn keyboard_driving_is_not_harsh() {
+        let mut app = analytics_app();
+        app.init_state::<BatteryStatus>()
+            .add_plugins(speed_simulation_plugin);
+        hold(&mut app, KeyCode::Space);
+        run_for(&mut app, 5.0);
+        release(&mut app, KeyCode::Space);
+        hold(&mut app, KeyCode::ArrowDown);
+        run_for(&mut app, 2.0);
+        let report = park(&mut app);
+        assert!(report.max_speed > 60.0);
+        assert_eq!(report.harsh_accelerations, 0);
+        assert_eq!(report.harsh_brakings, 0);
+    }
+
+    #[test]
+    fn battery_use_excludes_charging() {
+        let mut app = analytics_app();
+        set_speed(&mut app, 50.0);
+        run_for(&mut app, 1.0);
+        app.insert_resource(BatteryLevel(70.0));
+        run_for(&mut app, 1.0);
+        app.insert_resource(BatteryLevel(75.0));
+        run_for(&mut app, 1.0);
+        let report = park(&mut app);
+        assert!((report.energy_used - 0.1 * BATTERY_CAPACITY).abs() < 0.01);
+    }
+
+    #[test]
+    fn report_as_json() {
+        let report = Trip::default().report();
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["eco_score"], 100);
+        assert_eq!(json["speed_bands"][0]["from"], 0.0);
+        assert_eq!(json["speed_bands"][0]["to"], 30.0);
+    }
+}
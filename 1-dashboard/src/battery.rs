@@ -1,49 +1,56 @@
-use bevy::{
-    prelude::*,
-    render::render_resource::{AsBindGroup, ShaderType},
-    shader::ShaderRef,
-    sprite_render::{Material2d, Material2dPlugin},
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    gauge::GaugeMaterial,
+    input::keyboard_backend,
+    speed::Speed,
+    theme::{DashboardTheme, Palette},
+    widgets::{Widget, widget_enabled, widget_turned_on},
 };
 
-use crate::speed::Speed;
+/// Usable battery capacity in kWh
+pub const BATTERY_CAPACITY: f32 = 60.0;
+/// The simulation runs one minute per second, like the odometer
+pub const SIMULATED_HOURS_PER_SECOND: f32 = 1.0 / 60.0;
 
 pub fn battery_plugin(app: &mut App) {
-    app.add_plugins(Material2dPlugin::<BatteryMaterial>::default())
-        .init_state::<BatteryStatus>()
-        .add_systems(Startup, setup)
-        .add_systems(
-            Update,
-            (
-                update_battery.run_if(in_state(BatteryStatus::Unplugged)),
-                charging_battery.run_if(in_state(BatteryStatus::Charging)),
-                display_battery,
-            ),
+    app.add_plugins(battery_simulation_plugin).add_systems(
+        Update,
+        (
+            setup.run_if(widget_turned_on(Widget::Battery)),
+            (display_battery, apply_theme).run_if(widget_enabled(Widget::Battery)),
         )
-        .insert_resource(BatteryLevel(100.0));
+            .chain(),
+    );
 }
 
-#[derive(Asset, TypePath, AsBindGroup, ShaderType, Clone)]
-#[uniform(0, BatteryMaterial)]
-struct BatteryMaterial {
-    level: f32,
+/// Battery drained by the speed, kept while the battery widget is off
+pub fn battery_simulation_plugin(app: &mut App) {
+    app.init_state::<BatteryStatus>()
+        .init_resource::<BatteryLevel>()
+        .add_systems(
+            Update,
+            update_battery.run_if(in_state(BatteryStatus::Unplugged).and(keyboard_backend)),
+        );
 }
 
-impl<'a> From<&'a BatteryMaterial> for BatteryMaterial {
-    fn from(material: &'a BatteryMaterial) -> Self {
-        material.clone()
-    }
+fn set_palette(gauge: &mut GaugeMaterial, palette: &Palette) {
+    gauge.gradient = vec![(0.1, palette.battery_empty), (0.75, palette.battery_full)];
+    gauge.tick_color = palette.battery_marker;
 }
 
-impl Material2d for BatteryMaterial {
-    fn fragment_shader() -> ShaderRef {
-        "shaders/battery_bar.wgsl".into()
+/// State of charge in percent
+#[derive(Resource)]
+pub struct BatteryLevel(pub f32);
+
+impl Default for BatteryLevel {
+    fn default() -> Self {
+        Self(100.0)
     }
 }
 
-#[derive(Resource)]
-pub struct BatteryLevel(f32);
-
-#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, States, Default)]
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, States, Default, Serialize, Deserialize)]
 pub enum BatteryStatus {
     Charging,
     #[default]
@@ -54,11 +61,20 @@ fn setup(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
     mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<BatteryMaterial>>,
+    mut materials: ResMut<Assets<GaugeMaterial>>,
+    theme: Res<DashboardTheme>,
 ) {
+    // Colored by the level, with a marker in the middle
+    let mut material = GaugeMaterial {
+        value: 100.0,
+        gradient_by_value: true,
+        ticks: 2,
+        ..GaugeMaterial::linear(Color::NONE).with_range(0.0, 100.0)
+    };
+    set_palette(&mut material, &theme.palette);
+
     commands.spawn((
-        Transform::from_xyz(-500.0, -300.0, 0.0).with_scale(Vec3::splat(0.75)),
-        Visibility::Visible,
+        Widget::Battery,
         BatteryIndicator,
         children![
             (
@@ -75,8 +91,9 @@ fn setup(
             ),
             (
                 Mesh2d(meshes.add(Rectangle::new(300.0, 50.0))),
-                MeshMaterial2d(materials.add(BatteryMaterial { level: 1.0 })),
+                MeshMaterial2d(materials.add(material)),
                 Transform::from_xyz(250.0, 0.0, 0.0),
+                BatteryGauge,
             )
         ],
     ));
@@ -85,39 +102,32 @@ fn setup(
 #[derive(Component)]
 struct BatteryIndicator;
 
+#[derive(Component)]
+struct BatteryGauge;
+
 fn update_battery(
     mut battery: ResMut<BatteryLevel>,
-    speed: Res<Speed>,
+    speed: Option<Res<Speed>>,
     time: Res<Time>,
     mut next_state: ResMut<NextState<BatteryStatus>>,
 ) {
-    battery.0 = (battery.0 - (time.delta_secs() * (speed.0.powf(2.0)) / 1500.0)).max(0.0);
+    let speed = speed.map_or(0.0, |speed| speed.0);
+    battery.0 = (battery.0 - (time.delta_secs() * (speed.powf(2.0)) / 1500.0)).max(0.0);
     if battery.0 <= 0.0 {
         next_state.set(BatteryStatus::Charging);
     }
 }
 
-fn charging_battery(
-    mut battery: ResMut<BatteryLevel>,
-    time: Res<Time>,
-    mut next_state: ResMut<NextState<BatteryStatus>>,
-) {
-    battery.0 = (battery.0 + time.delta_secs() * 10.0).min(100.0);
-    if battery.0 >= 100.0 {
-        next_state.set(BatteryStatus::Unplugged);
-    }
-}
-
 fn display_battery(
     battery: Res<BatteryLevel>,
     indicator: Single<&Children, With<BatteryIndicator>>,
     mut visibility: Query<&mut Visibility>,
-    material: Single<&MeshMaterial2d<BatteryMaterial>>,
-    mut progress_materials: ResMut<Assets<BatteryMaterial>>,
+    material: Single<&MeshMaterial2d<GaugeMaterial>, With<BatteryGauge>>,
+    mut gauge_materials: ResMut<Assets<GaugeMaterial>>,
     battery_status: Res<State<BatteryStatus>>,
 ) {
     if battery.is_changed() {
-        progress_materials.get_mut(material.id()).unwrap().level = battery.0 / 100.0;
+        gauge_materials.get_mut(material.id()).unwrap().value = battery.0;
 
         match battery_status.get() {
             BatteryStatus::Charging => {
@@ -138,3 +148,77 @@ fn display_battery(
         }
     }
 }
+
+fn apply_theme(
+    theme: Res<DashboardTheme>,
+    material: Single<&MeshMaterial2d<GaugeMaterial>, With<BatteryGauge>>,
+    mut gauge_materials: ResMut<Assets<GaugeMaterial>>,
+) {
+    if theme.is_changed() {
+        set_palette(
+            gauge_materials.get_mut(material.id()).unwrap(),
+            &theme.palette,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{run_for, set_state, test_app};
+
+    fn battery_app(level: f32, speed: f32) -> App {
+        let mut app = test_app();
+        app.add_plugins(battery_simulation_plugin)
+            .insert_resource(BatteryLevel(level))
+            .insert_resource(Speed(speed));
+        app
+    }
+
+    fn level(app: &App) -> f32 {
+        app.world().resource::<BatteryLevel>().0
+    }
+
+    fn status(app: &App) -> BatteryStatus {
+        *app.world().resource::<State<BatteryStatus>>().get()
+    }
+
+    #[test]
+    fn no_drain_when_stopped() {
+        let mut app = battery_app(50.0, 0.0);
+        run_for(&mut app, 10.0);
+        assert_eq!(level(&app), 50.0);
+    }
+
+    #[test]
+    fn drains_faster_at_higher_speed() {
+        let mut slow = battery_app(100.0, 30.0);
+        let mut fast = battery_app(100.0, 90.0);
+        run_for(&mut slow, 1.0);
+        run_for(&mut fast, 1.0);
+
+        // Drain grows with the square of the speed
+        let slow_drain = 100.0 - level(&slow);
+        let fast_drain = 100.0 - level(&fast);
+        assert!(slow_drain > 0.0);
+        assert!((fast_drain / slow_drain - 9.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn empty_battery_starts_charging() {
+        let mut app = battery_app(0.5, 100.0);
+        assert_eq!(status(&app), BatteryStatus::Unplugged);
+        run_for(&mut app, 1.0);
+        assert_eq!(level(&app), 0.0);
+        assert_eq!(status(&app), BatteryStatus::Charging);
+    }
+
+    #[test]
+    fn no_drain_while_charging() {
+        let mut app = battery_app(50.0, 100.0);
+        set_state(&mut app, BatteryStatus::Charging);
+        let level_before = level(&app);
+        run_for(&mut app, 1.0);
+        assert_eq!(level(&app), level_before);
+    }
+}
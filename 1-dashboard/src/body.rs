@@ -0,0 +1,370 @@
+use bevy::{audio::Volume, prelude::*, sprite::Anchor};
+use serde::Deserialize;
+
+use crate::{
+    speed::Speed,
+    telltale::{Severity, Warning, WarningEvent},
+    theme::DashboardTheme,
+    widgets::{CAR_SIZE, Widget, car_silhouette, widget_enabled, widget_turned_on},
+};
+
+/// Speed in km/h above which an unbuckled seatbelt sounds the reminder
+const SEATBELT_SPEED: f32 = 10.0;
+// The reminder gets faster and louder the longer it is ignored:
+// seconds since it started, seconds between chimes, volume
+const SEATBELT_CHIME_STAGES: [(f32, f32, f32); 3] =
+    [(0.0, 2.0, 0.4), (20.0, 1.0, 0.7), (40.0, 0.5, 1.0)];
+const DOOR_OPEN_ANGLE: f32 = std::f32::consts::FRAC_PI_4;
+const DOOR_SIZE: Vec2 = vec2(6.0, 50.0);
+
+pub fn body_plugin(app: &mut App) {
+    app.add_plugins(body_simulation_plugin)
+        .add_systems(
+            Update,
+            (
+                setup.run_if(widget_turned_on(Widget::Doors)),
+                display.run_if(widget_enabled(Widget::Doors)),
+            )
+                .chain()
+                .after(remind_seatbelt),
+        )
+        .add_observer(play_seatbelt_chime);
+}
+
+/// Body controller driven from the keyboard, raising warnings for the `BodyState`
+pub fn body_simulation_plugin(app: &mut App) {
+    app.init_resource::<BodyState>().add_systems(
+        Update,
+        (control_body, detect_warnings, remind_seatbelt).chain(),
+    );
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Deserialize)]
+pub enum Door {
+    FrontLeft,
+    FrontRight,
+    RearLeft,
+    RearRight,
+}
+
+impl Door {
+    pub const ALL: [Door; 4] = [
+        Door::FrontLeft,
+        Door::FrontRight,
+        Door::RearLeft,
+        Door::RearRight,
+    ];
+
+    pub fn key(self) -> KeyCode {
+        match self {
+            Door::FrontLeft => KeyCode::Digit1,
+            Door::FrontRight => KeyCode::Digit2,
+            Door::RearLeft => KeyCode::Digit3,
+            Door::RearRight => KeyCode::Digit4,
+        }
+    }
+
+    /// Position of the hinge, on the side of the car silhouette
+    fn hinge(self) -> Vec2 {
+        let x = (CAR_SIZE.x + DOOR_SIZE.x) / 2.0;
+        match self {
+            Door::FrontLeft => vec2(-x, 40.0),
+            Door::FrontRight => vec2(x, 40.0),
+            Door::RearLeft => vec2(-x, -15.0),
+            Door::RearRight => vec2(x, -15.0),
+        }
+    }
+
+    /// Doors swing outward, clockwise on the left side
+    fn open_angle(self) -> f32 {
+        match self {
+            Door::FrontLeft | Door::RearLeft => -DOOR_OPEN_ANGLE,
+            Door::FrontRight | Door::RearRight => DOOR_OPEN_ANGLE,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Headlights {
+    #[default]
+    Off,
+    Low,
+    High,
+}
+
+impl Headlights {
+    fn next(self) -> Self {
+        match self {
+            Headlights::Off => Headlights::Low,
+            Headlights::Low => Headlights::High,
+            Headlights::High => Headlights::Off,
+        }
+    }
+}
+
+#[derive(Resource, Clone, PartialEq, Debug)]
+pub struct BodyState {
+    /// Open doors, in the order of `Door::ALL`
+    pub doors: [bool; 4],
+    /// Buckled seatbelts of the driver and the front passenger
+    pub seatbelts: [bool; 2],
+    pub headlights: Headlights,
+    pub fog_lights: bool,
+}
+
+impl Default for BodyState {
+    fn default() -> Self {
+        Self {
+            doors: [false; 4],
+            seatbelts: [true; 2],
+            headlights: Headlights::Off,
+            fog_lights: false,
+        }
+    }
+}
+
+impl BodyState {
+    pub fn door_open(&self, door: Door) -> bool {
+        self.doors[door as usize]
+    }
+
+    pub fn unbuckled(&self) -> bool {
+        self.seatbelts.contains(&false)
+    }
+
+    fn warnings(&self) -> [(Warning, bool); 5] {
+        [
+            (Warning::DoorOpen, self.doors.contains(&true)),
+            (Warning::Seatbelt, self.unbuckled()),
+            (Warning::LowBeam, self.headlights == Headlights::Low),
+            (Warning::HighBeam, self.headlights == Headlights::High),
+            (Warning::FogLights, self.fog_lights),
+        ]
+    }
+}
+
+/// Chime of the seatbelt reminder
+#[derive(Event, Clone, Copy, PartialEq, Debug)]
+pub struct SeatbeltChime {
+    pub volume: f32,
+}
+
+// 1 to 4 open and close the doors, B buckles the driver seatbelt, or the passenger one with
+// Shift, L switches the headlights between off, low and high beams, and O the fog lights
+fn control_body(input: Res<ButtonInput<KeyCode>>, mut body: ResMut<BodyState>) {
+    for door in Door::ALL {
+        if input.just_pressed(door.key()) {
+            body.doors[door as usize] ^= true;
+        }
+    }
+    if input.just_pressed(KeyCode::KeyB) {
+        let seat = if input.pressed(KeyCode::ShiftLeft) || input.pressed(KeyCode::ShiftRight) {
+            1
+        } else {
+            0
+        };
+        body.seatbelts[seat] ^= true;
+    }
+    if input.just_pressed(KeyCode::KeyL) {
+        body.headlights = body.headlights.next();
+    }
+    if input.just_pressed(KeyCode::KeyO) {
+        body.fog_lights ^= true;
+    }
+}
+
+// Raising an active warning or clearing an inactive one does nothing, so all are sent on changes
+fn detect_warnings(mut commands: Commands, body: Res<BodyState>) {
+    if !body.is_changed() {
+        return;
+    }
+    for (warning, active) in body.warnings() {
+        commands.trigger(if active {
+            WarningEvent::Raise(warning)
+        } else {
+            WarningEvent::Clear(warning)
+        });
+    }
+}
+
+/// Time since the reminder started, and until its next chime
+#[derive(Default)]
+struct SeatbeltReminder {
+    elapsed: f32,
+    next_chime: f32,
+}
+
+fn remind_seatbelt(
+    mut commands: Commands,
+    body: Res<BodyState>,
+    speed: Option<Res<Speed>>,
+    time: Res<Time>,
+    mut reminder: Local<Option<SeatbeltReminder>>,
+) {
+    let moving = speed.is_some_and(|speed| speed.0.abs() > SEATBELT_SPEED);
+    if !moving || !body.unbuckled() {
+        *reminder = None;
+        return;
+    }
+
+    let reminder = reminder.get_or_insert_default();
+    reminder.next_chime -= time.delta_secs();
+    if reminder.next_chime <= 0.0 {
+        let (_, interval, volume) = SEATBELT_CHIME_STAGES
+            .into_iter()
+            .rfind(|(from, _, _)| *from <= reminder.elapsed)
+            .unwrap();
+        commands.trigger(SeatbeltChime { volume });
+        reminder.next_chime += interval;
+    }
+    reminder.elapsed += time.delta_secs();
+}
+
+fn play_seatbelt_chime(
+    chime: On<SeatbeltChime>,
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+) {
+    commands.spawn((
+        AudioPlayer::new(asset_server.load("warnings/chime.wav")),
+        PlaybackSettings::DESPAWN.with_volume(Volume::Linear(chime.volume)),
+    ));
+}
+
+#[derive(Component)]
+struct DoorSprite(Door);
+
+#[derive(Component)]
+struct SeatSprite(usize);
+
+fn setup(mut commands: Commands) {
+    commands.spawn(Widget::Doors).with_children(|parent| {
+        parent.spawn(car_silhouette());
+        for door in Door::ALL {
+            parent.spawn((
+                Sprite::from_color(Color::WHITE, DOOR_SIZE),
+                // Rotates around the hinge, at the front of the door
+                Anchor::TOP_CENTER,
+                Transform::from_translation(door.hinge().extend(0.1)),
+                DoorSprite(door),
+            ));
+        }
+        for (seat, x) in [(0, -18.0), (1, 18.0)] {
+            parent.spawn((
+                Sprite::from_color(Color::WHITE, Vec2::splat(24.0)),
+                Transform::from_xyz(x, 10.0, 0.1),
+                SeatSprite(seat),
+            ));
+        }
+    });
+}
+
+fn display(
+    body: Res<BodyState>,
+    theme: Res<DashboardTheme>,
+    mut doors: Query<(&DoorSprite, &mut Sprite, &mut Transform), Without<SeatSprite>>,
+    mut seats: Query<(&SeatSprite, &mut Sprite), Without<DoorSprite>>,
+) {
+    for (door, mut sprite, mut transform) in &mut doors {
+        let open = body.door_open(door.0);
+        transform.rotation = Quat::from_rotation_z(if open { door.0.open_angle() } else { 0.0 });
+        sprite.color = if open {
+            Severity::Caution.color()
+        } else {
+            theme.palette.text
+        };
+    }
+    for (seat, mut sprite) in &mut seats {
+        sprite.color = if body.seatbelts[seat.0] {
+            theme.palette.signal_off
+        } else {
+            Severity::Critical.color()
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{hold, record, record_as, recorded, run_for, tap, test_app};
+
+    fn body_app(speed: f32) -> App {
+        let mut app = test_app();
+        app.add_plugins(body_simulation_plugin)
+            .insert_resource(Speed(speed));
+        record_as(&mut app, |chime: &SeatbeltChime| chime.volume);
+        record::<WarningEvent>(&mut app);
+        app
+    }
+
+    fn raised(app: &App) -> Vec<Warning> {
+        let mut raised = Vec::new();
+        for event in recorded::<WarningEvent>(app) {
+            match *event {
+                WarningEvent::Raise(warning) if !raised.contains(&warning) => raised.push(warning),
+                WarningEvent::Clear(warning) => raised.retain(|raised| *raised != warning),
+                _ => (),
+            }
+        }
+        raised
+    }
+
+    #[test]
+    fn keys_drive_the_body_state() {
+        let mut app = body_app(0.0);
+        tap(&mut app, KeyCode::Digit3);
+        tap(&mut app, KeyCode::KeyL);
+        tap(&mut app, KeyCode::KeyL);
+        tap(&mut app, KeyCode::KeyO);
+        hold(&mut app, KeyCode::ShiftLeft);
+        tap(&mut app, KeyCode::KeyB);
+
+        let body = app.world().resource::<BodyState>();
+        assert!(body.door_open(Door::RearLeft));
+        assert!(!body.door_open(Door::FrontLeft));
+        assert_eq!(body.headlights, Headlights::High);
+        assert!(body.fog_lights);
+        assert_eq!(body.seatbelts, [true, false]);
+        assert_eq!(
+            raised(&app),
+            [
+                Warning::DoorOpen,
+                Warning::HighBeam,
+                Warning::FogLights,
+                Warning::Seatbelt
+            ]
+        );
+    }
+
+    #[test]
+    fn no_chime_when_buckled_or_slow() {
+        let mut app = body_app(50.0);
+        run_for(&mut app, 5.0);
+        app.insert_resource(Speed(5.0));
+        tap(&mut app, KeyCode::KeyB);
+        run_for(&mut app, 5.0);
+        assert!(recorded::<f32>(&app).is_empty());
+    }
+
+    #[test]
+    fn seatbelt_chime_escalates() {
+        let mut app = body_app(50.0);
+        tap(&mut app, KeyCode::KeyB);
+        run_for(&mut app, 9.0);
+        let chimes = &recorded::<f32>(&app);
+        assert_eq!(chimes.len(), 5);
+        assert!(chimes.iter().all(|volume| *volume == 0.4));
+
+        run_for(&mut app, 41.0);
+        let chimes = &recorded::<f32>(&app);
+        assert_eq!(chimes.last(), Some(&1.0));
+        // 5 more at the first stage, 20 at the second and 20 at the last
+        assert!((48..=52).contains(&chimes.len()));
+
+        // Buckling stops the reminder
+        tap(&mut app, KeyCode::KeyB);
+        let count = recorded::<f32>(&app).len();
+        run_for(&mut app, 5.0);
+        assert_eq!(recorded::<f32>(&app).len(), count);
+    }
+}
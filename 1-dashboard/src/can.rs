@@ -0,0 +1,445 @@
+use std::{
+    fs::File,
+    io::{BufRead, BufReader},
+    thread,
+    time::{Duration, Instant},
+};
+
+use bevy::{
+    asset::{AssetLoader, LoadContext, io::Reader},
+    platform::collections::HashMap,
+    prelude::*,
+};
+use crossbeam::channel::{self, Receiver, Sender};
+use serde::Deserialize;
+
+use crate::{
+    input::{InputBackend, VehicleData},
+    telltale::{Warning, WarningEvent},
+    turn::TurnSignal,
+};
+
+pub fn can_plugin(app: &mut App) {
+    let Some(source) = CanSource::from_args() else {
+        return;
+    };
+    app.init_asset::<CanMapping>()
+        .init_asset_loader::<CanMappingLoader>()
+        .insert_resource(source)
+        .add_systems(Startup, start_reader)
+        .add_systems(
+            Update,
+            apply_can_frames.run_if(resource_exists::<CanFrames>),
+        );
+}
+
+/// Source of CAN frames, selected with `--can-log <file>` or `--can-interface <name>`
+#[derive(Resource, Clone, Debug)]
+enum CanSource {
+    /// A log file recorded with `candump -l`, replayed with its original timing
+    Log(String),
+    /// A SocketCAN interface, like `vcan0`
+    Interface(String),
+}
+
+impl CanSource {
+    fn from_args() -> Option<Self> {
+        let args: Vec<String> = std::env::args().collect();
+        args.windows(2).find_map(|arg| match arg[0].as_str() {
+            "--can-log" => Some(CanSource::Log(arg[1].clone())),
+            "--can-interface" => Some(CanSource::Interface(arg[1].clone())),
+            _ => None,
+        })
+    }
+}
+
+#[derive(Debug)]
+struct CanFrame {
+    id: u32,
+    data: Vec<u8>,
+}
+
+#[derive(Resource)]
+struct CanFrames(Receiver<CanFrame>);
+
+#[derive(Deserialize, Clone, Copy, Hash, PartialEq, Eq, Debug)]
+enum VehicleSignal {
+    /// Speed in km/h
+    Speed,
+    /// Battery state of charge in percent
+    StateOfCharge,
+    /// Non-zero while a charger is plugged in
+    Charging,
+    /// 0 for off, 1 for left, 2 for right
+    Indicators,
+}
+
+/// DBC-like description of where each signal is in the CAN frames
+#[derive(Asset, TypePath, Deserialize)]
+struct CanMapping {
+    signals: HashMap<VehicleSignal, SignalMapping>,
+}
+
+/// A little endian signal, decoded as `raw * factor + offset`
+#[derive(Deserialize)]
+struct SignalMapping {
+    id: u32,
+    start_bit: u32,
+    length: u32,
+    #[serde(default = "default_factor")]
+    factor: f32,
+    #[serde(default)]
+    offset: f32,
+}
+
+fn default_factor() -> f32 {
+    1.0
+}
+
+impl SignalMapping {
+    fn decode(&self, data: &[u8]) -> f32 {
+        let mut bytes = [0; 8];
+        let len = data.len().min(8);
+        bytes[..len].copy_from_slice(&data[..len]);
+        let mask = if self.length >= 64 {
+            u64::MAX
+        } else {
+            (1 << self.length) - 1
+        };
+        let raw = u64::from_le_bytes(bytes)
+            .checked_shr(self.start_bit)
+            .unwrap_or(0)
+            & mask;
+        raw as f32 * self.factor + self.offset
+    }
+
+    // Signals have to fit in the 8 bytes of a classic CAN frame
+    fn validate(&self, signal: VehicleSignal) -> Result<(), BevyError> {
+        let end = self.start_bit.checked_add(self.length);
+        if self.length == 0 || end.is_none_or(|end| end > 64) {
+            return Err(format!(
+                "{signal:?} signal of {} bits at bit {} doesn't fit in a CAN frame",
+                self.length, self.start_bit
+            )
+            .into());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Default, TypePath)]
+struct CanMappingLoader;
+
+impl AssetLoader for CanMappingLoader {
+    type Asset = CanMapping;
+    type Settings = ();
+    type Error = BevyError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let mapping: CanMapping = ron::de::from_bytes(&bytes)?;
+        for (signal, signal_mapping) in &mapping.signals {
+            signal_mapping.validate(*signal)?;
+        }
+        Ok(mapping)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["can.ron"]
+    }
+}
+
+#[derive(Resource)]
+struct Mapping(Handle<CanMapping>);
+
+fn start_reader(
+    mut commands: Commands,
+    source: Res<CanSource>,
+    asset_server: Res<AssetServer>,
+    mut backend: ResMut<InputBackend>,
+) {
+    let (sender, receiver) = channel::unbounded();
+    let started = match source.clone() {
+        CanSource::Log(path) => File::open(&path).map(|file| {
+            thread::spawn(move || replay_log(BufReader::new(file), sender));
+        }),
+        CanSource::Interface(interface) => socketcan::open(&interface).map(|socket| {
+            thread::spawn(move || socketcan::read_frames(socket, sender));
+        }),
+    };
+
+    match started {
+        Ok(()) => {
+            info!("Reading vehicle data from CAN {:?}", *source);
+            commands.insert_resource(CanFrames(receiver));
+            commands.insert_resource(Mapping(asset_server.load("vehicle.can.ron")));
+            *backend = InputBackend::Can;
+        }
+        Err(error) => {
+            error!(
+                "Couldn't open CAN {:?}, using the keyboard: {error}",
+                *source
+            );
+            commands.trigger(WarningEvent::Raise(Warning::SystemFault));
+        }
+    }
+}
+
+fn replay_log(log: impl BufRead, sender: Sender<CanFrame>) {
+    let start = Instant::now();
+    let mut first_timestamp = None;
+    for line in log.lines().map_while(Result::ok) {
+        let Some((timestamp, frame)) = parse_candump_line(&line) else {
+            continue;
+        };
+        let first_timestamp = *first_timestamp.get_or_insert(timestamp);
+        let due = Duration::from_secs_f64((timestamp - first_timestamp).max(0.0));
+        thread::sleep(due.saturating_sub(start.elapsed()));
+        if sender.send(frame).is_err() {
+            return;
+        }
+    }
+}
+
+// Parse a line in the `candump -l` format: `(1700000000.000000) vcan0 123#0011223344`
+fn parse_candump_line(line: &str) -> Option<(f64, CanFrame)> {
+    let mut parts = line.split_whitespace();
+    let timestamp = parts
+        .next()?
+        .strip_prefix('(')?
+        .strip_suffix(')')?
+        .parse()
+        .ok()?;
+    let _interface = parts.next()?;
+    let (id, data) = parts.next()?.split_once('#')?;
+    // Remote and CAN FD frames are not used
+    if data.starts_with(['R', '#']) {
+        return None;
+    }
+    let id = u32::from_str_radix(id, 16).ok()?;
+    let data = (0..data.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(data.get(i..i + 2)?, 16).ok())
+        .collect::<Option<Vec<_>>>()?;
+    Some((timestamp, CanFrame { id, data }))
+}
+
+#[cfg(target_os = "linux")]
+mod socketcan {
+    use std::{
+        ffi::CString,
+        fs::File,
+        io::{self, Read},
+        mem,
+        os::fd::FromRawFd,
+    };
+
+    use crossbeam::channel::Sender;
+
+    use super::CanFrame;
+
+    pub fn open(interface: &str) -> io::Result<File> {
+        let name = CString::new(interface).map_err(io::Error::other)?;
+        // SAFETY: plain libc calls, the file descriptor is closed by `File` once owned
+        unsafe {
+            let index = libc::if_nametoindex(name.as_ptr());
+            if index == 0 {
+                return Err(io::Error::last_os_error());
+            }
+            let fd = libc::socket(libc::PF_CAN, libc::SOCK_RAW, libc::CAN_RAW);
+            if fd < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            let socket = File::from_raw_fd(fd);
+
+            let mut address: libc::sockaddr_can = mem::zeroed();
+            address.can_family = libc::AF_CAN as libc::sa_family_t;
+            address.can_ifindex = index as libc::c_int;
+            if libc::bind(
+                fd,
+                &address as *const libc::sockaddr_can as *const libc::sockaddr,
+                mem::size_of::<libc::sockaddr_can>() as libc::socklen_t,
+            ) < 0
+            {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(socket)
+        }
+    }
+
+    pub fn read_frames(mut socket: File, sender: Sender<CanFrame>) {
+        let mut buffer = [0; libc::CAN_MTU];
+        while socket.read_exact(&mut buffer).is_ok() {
+            let can_id = u32::from_ne_bytes(buffer[0..4].try_into().unwrap());
+            // Error and remote frames don't carry signals
+            if can_id & (libc::CAN_ERR_FLAG | libc::CAN_RTR_FLAG) != 0 {
+                continue;
+            }
+            let len = (buffer[4] as usize).min(8);
+            let frame = CanFrame {
+                id: can_id & libc::CAN_EFF_MASK,
+                data: buffer[8..8 + len].to_vec(),
+            };
+            if sender.send(frame).is_err() {
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod socketcan {
+    use std::{fs::File, io};
+
+    use crossbeam::channel::Sender;
+
+    use super::CanFrame;
+
+    pub fn open(_interface: &str) -> io::Result<File> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "SocketCAN is only available on Linux",
+        ))
+    }
+
+    pub fn read_frames(_socket: File, _sender: Sender<CanFrame>) {}
+}
+
+fn apply_can_frames(
+    mut commands: Commands,
+    frames: Res<CanFrames>,
+    mapping: Res<Mapping>,
+    mappings: Res<Assets<CanMapping>>,
+    mut last_indicators: Local<Option<u8>>,
+) {
+    // Keep the frames queued until the mapping is loaded
+    let Some(mapping) = mappings.get(&mapping.0) else {
+        return;
+    };
+
+    for frame in frames.0.try_iter() {
+        for (signal, signal_mapping) in &mapping.signals {
+            if signal_mapping.id != frame.id {
+                continue;
+            }
+            let value = signal_mapping.decode(&frame.data);
+            match signal {
+                VehicleSignal::Speed => commands.trigger(VehicleData::Speed(value)),
+                VehicleSignal::StateOfCharge => {
+                    commands.trigger(VehicleData::StateOfCharge(value));
+                }
+                VehicleSignal::Charging => commands.trigger(VehicleData::Charging(value != 0.0)),
+                VehicleSignal::Indicators => {
+                    let indicators = value as u8;
+                    if *last_indicators == Some(indicators) {
+                        continue;
+                    }
+                    *last_indicators = Some(indicators);
+                    let signal = match indicators {
+                        0 => TurnSignal::Stop,
+                        1 => TurnSignal::Left,
+                        2 => TurnSignal::Right,
+                        _ => {
+                            warn!("Unknown indicators value {indicators}");
+                            continue;
+                        }
+                    };
+                    commands.trigger(VehicleData::TurnSignal(signal));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mapping(start_bit: u32, length: u32, factor: f32, offset: f32) -> SignalMapping {
+        SignalMapping {
+            id: 0x123,
+            start_bit,
+            length,
+            factor,
+            offset,
+        }
+    }
+
+    #[test]
+    fn candump_lines() {
+        let (timestamp, frame) = parse_candump_line("(1760000000.250000) vcan0 3A0#A401").unwrap();
+        assert_eq!(timestamp, 1760000000.25);
+        assert_eq!(frame.id, 0x3a0);
+        assert_eq!(frame.data, [0xa4, 0x01]);
+
+        let (_, frame) = parse_candump_line("(1760000000.000000) vcan0 2F0#").unwrap();
+        assert!(frame.data.is_empty());
+    }
+
+    #[test]
+    fn short_and_malformed_candump_lines() {
+        assert!(parse_candump_line("").is_none());
+        assert!(parse_candump_line("(1760000000.000000) vcan0").is_none());
+        assert!(parse_candump_line("1760000000.000000 vcan0 123#00").is_none());
+        assert!(parse_candump_line("(1760000000.000000) vcan0 123").is_none());
+        assert!(parse_candump_line("(1760000000.000000) vcan0 XYZ#00").is_none());
+        // Odd number of digits and invalid bytes
+        assert!(parse_candump_line("(1760000000.000000) vcan0 123#001").is_none());
+        assert!(parse_candump_line("(1760000000.000000) vcan0 123#0G").is_none());
+        // Remote and CAN FD frames
+        assert!(parse_candump_line("(1760000000.000000) vcan0 123#R").is_none());
+        assert!(parse_candump_line("(1760000000.000000) vcan0 123##1AA").is_none());
+    }
+
+    #[test]
+    fn decode_with_factor_and_offset() {
+        // Little endian 0x1234 is 4660
+        assert_eq!(mapping(0, 16, 0.01, 0.0).decode(&[0x34, 0x12]), 46.6);
+        assert_eq!(mapping(8, 8, 0.5, -10.0).decode(&[0xff, 0x64]), 40.0);
+        assert_eq!(mapping(9, 1, 1.0, 0.0).decode(&[0x00, 0x02]), 1.0);
+        // Missing bytes are zeros
+        assert_eq!(mapping(16, 8, 1.0, 5.0).decode(&[0x01]), 5.0);
+        assert_eq!(mapping(0, 64, 1.0, 0.0).decode(&[0xff; 8]), u64::MAX as f32);
+    }
+
+    #[test]
+    fn signals_out_of_the_frame_are_rejected() {
+        assert!(
+            mapping(0, 64, 1.0, 0.0)
+                .validate(VehicleSignal::Speed)
+                .is_ok()
+        );
+        assert!(
+            mapping(56, 8, 1.0, 0.0)
+                .validate(VehicleSignal::Speed)
+                .is_ok()
+        );
+        assert!(
+            mapping(57, 8, 1.0, 0.0)
+                .validate(VehicleSignal::Speed)
+                .is_err()
+        );
+        assert!(
+            mapping(64, 1, 1.0, 0.0)
+                .validate(VehicleSignal::Speed)
+                .is_err()
+        );
+        assert!(
+            mapping(0, 0, 1.0, 0.0)
+                .validate(VehicleSignal::Speed)
+                .is_err()
+        );
+        assert!(
+            mapping(u32::MAX, 2, 1.0, 0.0)
+                .validate(VehicleSignal::Speed)
+                .is_err()
+        );
+        assert_eq!(mapping(64, 8, 1.0, 0.0).decode(&[0xff; 8]), 0.0);
+    }
+}
@@ -0,0 +1,436 @@
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+
+use crate::{
+    battery::{BATTERY_CAPACITY, BatteryLevel, BatteryStatus, SIMULATED_HOURS_PER_SECOND},
+    input::keyboard_backend,
+    speed::Speed,
+    theme::{DashboardTheme, Themed},
+    widgets::{Widget, widget_enabled, widget_turned_on},
+};
+
+const CHART_SIZE: Vec2 = vec2(240.0, 60.0);
+const CHART_SAMPLES: usize = 120;
+const CHART_SAMPLE_INTERVAL: f32 = 0.5;
+
+pub fn charging_plugin(app: &mut App) {
+    app.add_plugins(charging_simulation_plugin).add_systems(
+        Update,
+        (
+            setup.run_if(widget_turned_on(Widget::Charging)),
+            (display_session, draw_power_chart).run_if(widget_enabled(Widget::Charging)),
+        )
+            .chain(),
+    );
+}
+
+/// Charging sessions started from the keyboard, filling the `BatteryLevel`
+pub fn charging_simulation_plugin(app: &mut App) {
+    app.insert_resource(ChargeLimit(90.0))
+        .add_systems(
+            Update,
+            (
+                (plug_charger, set_charge_limit).run_if(keyboard_backend),
+                (charge_battery.run_if(keyboard_backend), track_session)
+                    .chain()
+                    .run_if(
+                        in_state(BatteryStatus::Charging)
+                            .and(resource_exists::<ChargingSession>)
+                            .and(resource_exists::<BatteryLevel>),
+                    ),
+            )
+                .chain(),
+        )
+        .add_systems(OnEnter(BatteryStatus::Charging), start_session)
+        .add_systems(OnExit(BatteryStatus::Charging), end_session);
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ChargerType {
+    /// Wall box or public AC charger
+    Ac,
+    /// Fast DC charger
+    Dc,
+}
+
+impl ChargerType {
+    fn max_power(self) -> f32 {
+        match self {
+            ChargerType::Ac => 11.0,
+            ChargerType::Dc => 150.0,
+        }
+    }
+
+    /// Power in kW at a given state of charge, tapering as the battery fills up
+    fn power(self, level: f32) -> f32 {
+        let taper = match self {
+            ChargerType::Ac if level > 95.0 => 1.0 - 0.7 * (level - 95.0) / 5.0,
+            ChargerType::Dc if level > 50.0 => 1.0 - 0.8 * (level - 50.0) / 50.0,
+            _ => 1.0,
+        };
+        self.max_power() * taper
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            ChargerType::Ac => "AC",
+            ChargerType::Dc => "DC",
+        }
+    }
+}
+
+/// State of charge in percent at which charging stops
+#[derive(Resource)]
+pub struct ChargeLimit(pub f32);
+
+#[derive(Resource)]
+pub struct ChargingSession {
+    pub charger: ChargerType,
+    /// Current power in kW
+    pub power: f32,
+    /// Energy added since the start of the session in kWh
+    pub energy_added: f32,
+    last_level: Option<f32>,
+    history: VecDeque<f32>,
+    sample_timer: Timer,
+}
+
+impl ChargingSession {
+    fn new(charger: ChargerType) -> Self {
+        Self {
+            charger,
+            power: 0.0,
+            energy_added: 0.0,
+            last_level: None,
+            history: VecDeque::with_capacity(CHART_SAMPLES),
+            sample_timer: Timer::from_seconds(CHART_SAMPLE_INTERVAL, TimerMode::Repeating),
+        }
+    }
+}
+
+fn plug_charger(
+    mut commands: Commands,
+    input: Res<ButtonInput<KeyCode>>,
+    battery_status: Res<State<BatteryStatus>>,
+    mut next_battery_status: ResMut<NextState<BatteryStatus>>,
+    speed: Option<Res<Speed>>,
+) {
+    if !input.just_pressed(KeyCode::KeyP) {
+        return;
+    }
+
+    match battery_status.get() {
+        BatteryStatus::Charging => next_battery_status.set(BatteryStatus::Unplugged),
+        BatteryStatus::Unplugged => {
+            if speed.is_some_and(|speed| speed.0.abs() >= 1.0) {
+                info!("Stop the car before plugging a charger");
+                return;
+            }
+            let charger = if input.pressed(KeyCode::ShiftLeft) || input.pressed(KeyCode::ShiftRight)
+            {
+                ChargerType::Dc
+            } else {
+                ChargerType::Ac
+            };
+            commands.insert_resource(ChargingSession::new(charger));
+            next_battery_status.set(BatteryStatus::Charging);
+        }
+    }
+}
+
+fn set_charge_limit(input: Res<ButtonInput<KeyCode>>, mut limit: ResMut<ChargeLimit>) {
+    if input.just_pressed(KeyCode::BracketLeft) {
+        limit.0 = (limit.0 - 5.0).max(50.0);
+    }
+    if input.just_pressed(KeyCode::BracketRight) {
+        limit.0 = (limit.0 + 5.0).min(100.0);
+    }
+}
+
+// Sessions started by other means use an AC charger, except for an empty battery which gets
+// a fast charger so that the car is soon back on the road
+fn start_session(
+    mut commands: Commands,
+    session: Option<Res<ChargingSession>>,
+    battery: Option<Res<BatteryLevel>>,
+) {
+    if session.is_none() {
+        let charger = if battery.is_some_and(|battery| battery.0 <= 0.0) {
+            ChargerType::Dc
+        } else {
+            ChargerType::Ac
+        };
+        commands.insert_resource(ChargingSession::new(charger));
+    }
+}
+
+fn end_session(mut commands: Commands) {
+    commands.remove_resource::<ChargingSession>();
+}
+
+fn charge_battery(
+    mut battery: ResMut<BatteryLevel>,
+    session: Res<ChargingSession>,
+    limit: Res<ChargeLimit>,
+    time: Res<Time>,
+    mut next_battery_status: ResMut<NextState<BatteryStatus>>,
+) {
+    let energy = session.charger.power(battery.0) * time.delta_secs() * SIMULATED_HOURS_PER_SECOND;
+    battery.0 = (battery.0 + energy / BATTERY_CAPACITY * 100.0).min(100.0);
+    if battery.0 >= limit.0 {
+        next_battery_status.set(BatteryStatus::Unplugged);
+    }
+}
+
+// Measures the session from the state of charge, so it also works with vehicle data from CAN
+fn track_session(
+    battery: Res<BatteryLevel>,
+    mut session: ResMut<ChargingSession>,
+    time: Res<Time>,
+) {
+    let delta = time.delta_secs();
+    if let Some(last_level) = session.last_level
+        && delta > 0.0
+    {
+        let energy = (battery.0 - last_level).max(0.0) / 100.0 * BATTERY_CAPACITY;
+        session.energy_added += energy;
+        let power = energy / (delta * SIMULATED_HOURS_PER_SECOND);
+        session.power.smooth_nudge(&power, 5.0, delta);
+    }
+    session.last_level = Some(battery.0);
+
+    if session.sample_timer.tick(time.delta()).just_finished() {
+        if session.history.len() == CHART_SAMPLES {
+            session.history.pop_front();
+        }
+        let power = session.power;
+        session.history.push_back(power);
+    }
+}
+
+#[derive(Component)]
+struct ChargingPanel;
+
+#[derive(Component)]
+struct ChargingText;
+
+#[derive(Component)]
+struct PowerChart;
+
+fn setup(mut commands: Commands) {
+    commands.spawn((
+        Widget::Charging,
+        children![(
+            ChargingPanel,
+            Visibility::Hidden,
+            Transform::default(),
+            children![
+                (
+                    Text2d::new(""),
+                    TextFont::from_font_size(20.0),
+                    Transform::from_xyz(0.0, 40.0, 0.0),
+                    Themed::Text,
+                    ChargingText,
+                ),
+                (Transform::from_xyz(0.0, -30.0, 0.0), PowerChart),
+            ],
+        )],
+    ));
+}
+
+fn display_session(
+    session: Option<Res<ChargingSession>>,
+    battery: Option<Res<BatteryLevel>>,
+    limit: Res<ChargeLimit>,
+    mut panel: Single<&mut Visibility, With<ChargingPanel>>,
+    mut text: Single<&mut Text2d, With<ChargingText>>,
+) {
+    let (Some(session), Some(battery)) = (session, battery) else {
+        **panel = Visibility::Hidden;
+        return;
+    };
+    **panel = Visibility::Inherited;
+
+    let remaining_energy = (limit.0 - battery.0).max(0.0) / 100.0 * BATTERY_CAPACITY;
+    let time_to_limit = if remaining_energy <= 0.0 {
+        "done".to_string()
+    } else if session.power > 0.1 {
+        let minutes = (remaining_energy / session.power * 60.0).ceil() as u32;
+        format!("{}h{:02} to {:.0}%", minutes / 60, minutes % 60, limit.0)
+    } else {
+        format!("-- to {:.0}%", limit.0)
+    };
+    text.0 = format!(
+        "{} {:>5.1} kW\n+{:.1} kWh\n{}",
+        session.charger.label(),
+        session.power,
+        session.energy_added,
+        time_to_limit
+    );
+}
+
+fn draw_power_chart(
+    mut gizmos: Gizmos,
+    session: Option<Res<ChargingSession>>,
+    chart: Single<(&GlobalTransform, &InheritedVisibility), With<PowerChart>>,
+    theme: Res<DashboardTheme>,
+) {
+    let (transform, visibility) = *chart;
+    let Some(session) = session else {
+        return;
+    };
+    if !visibility.get() {
+        return;
+    }
+
+    let to_world = |point: Vec2| transform.transform_point(point.extend(0.0)).truncate();
+    let origin = -CHART_SIZE / 2.0;
+    gizmos.linestrip_2d(
+        [
+            origin + vec2(0.0, CHART_SIZE.y),
+            origin,
+            origin + vec2(CHART_SIZE.x, 0.0),
+        ]
+        .map(to_world),
+        theme.palette.secondary_text,
+    );
+    let step = CHART_SIZE.x / (CHART_SAMPLES - 1) as f32;
+    let max_power = session.charger.max_power();
+    gizmos.linestrip_2d(
+        session.history.iter().enumerate().map(|(i, power)| {
+            to_world(origin + vec2(i as f32 * step, power / max_power * CHART_SIZE.y))
+        }),
+        theme.palette.progress,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        battery::battery_simulation_plugin,
+        testing::{FRAME_DURATION, hold, run_for, set_state, tap, test_app},
+    };
+
+    fn charging_app(level: f32, speed: f32) -> App {
+        let mut app = test_app();
+        app.add_plugins((battery_simulation_plugin, charging_simulation_plugin))
+            .insert_resource(BatteryLevel(level))
+            .insert_resource(Speed(speed));
+        app
+    }
+
+    fn status(app: &App) -> BatteryStatus {
+        *app.world().resource::<State<BatteryStatus>>().get()
+    }
+
+    fn charger(app: &App) -> Option<ChargerType> {
+        app.world()
+            .get_resource::<ChargingSession>()
+            .map(|session| session.charger)
+    }
+
+    #[test]
+    fn plug_and_unplug_charger() {
+        let mut app = charging_app(50.0, 0.0);
+        tap(&mut app, KeyCode::KeyP);
+        app.update();
+        assert_eq!(status(&app), BatteryStatus::Charging);
+        assert_eq!(charger(&app), Some(ChargerType::Ac));
+
+        tap(&mut app, KeyCode::KeyP);
+        app.update();
+        assert_eq!(status(&app), BatteryStatus::Unplugged);
+        assert_eq!(charger(&app), None);
+    }
+
+    #[test]
+    fn shift_plugs_fast_charger() {
+        let mut app = charging_app(50.0, 0.0);
+        hold(&mut app, KeyCode::ShiftLeft);
+        tap(&mut app, KeyCode::KeyP);
+        app.update();
+        assert_eq!(charger(&app), Some(ChargerType::Dc));
+    }
+
+    #[test]
+    fn cannot_plug_while_moving() {
+        let mut app = charging_app(50.0, 30.0);
+        tap(&mut app, KeyCode::KeyP);
+        app.update();
+        assert_eq!(status(&app), BatteryStatus::Unplugged);
+        assert_eq!(charger(&app), None);
+    }
+
+    #[test]
+    fn empty_battery_fast_charges() {
+        let mut app = charging_app(0.5, 100.0);
+        run_for(&mut app, 1.0);
+        assert_eq!(status(&app), BatteryStatus::Charging);
+        assert_eq!(charger(&app), Some(ChargerType::Dc));
+
+        // Half a minute instead of the five an AC charger would take
+        app.insert_resource(Speed(0.0));
+        run_for(&mut app, 30.0);
+        assert_eq!(status(&app), BatteryStatus::Unplugged);
+        assert!(app.world().resource::<BatteryLevel>().0 >= 90.0);
+    }
+
+    #[test]
+    fn charging_stops_at_limit() {
+        let mut app = charging_app(85.0, 0.0);
+        hold(&mut app, KeyCode::ShiftLeft);
+        tap(&mut app, KeyCode::KeyP);
+        run_for(&mut app, 10.0);
+        assert_eq!(status(&app), BatteryStatus::Unplugged);
+        let level = app.world().resource::<BatteryLevel>().0;
+        assert!((90.0..91.0).contains(&level));
+    }
+
+    #[test]
+    fn session_tracks_energy_and_power() {
+        let mut app = charging_app(20.0, 0.0);
+        hold(&mut app, KeyCode::ShiftLeft);
+        tap(&mut app, KeyCode::KeyP);
+        run_for(&mut app, 2.0);
+
+        let session = app.world().resource::<ChargingSession>();
+        let added = (app.world().resource::<BatteryLevel>().0 - 20.0) / 100.0 * BATTERY_CAPACITY;
+        // The level before the first frame of charging is not known by the session
+        let first_frame = ChargerType::Dc.max_power() * FRAME_DURATION * SIMULATED_HOURS_PER_SECOND;
+        assert!(session.energy_added <= added);
+        assert!(session.energy_added >= added - first_frame - 0.001);
+        // The power is smoothed toward the full DC power, available under 50%
+        assert!(session.power > 100.0);
+        assert!(session.power <= ChargerType::Dc.max_power() + 0.1);
+    }
+
+    #[test]
+    fn other_sessions_use_ac_charger() {
+        let mut app = charging_app(50.0, 0.0);
+        set_state(&mut app, BatteryStatus::Charging);
+        assert_eq!(charger(&app), Some(ChargerType::Ac));
+    }
+
+    #[test]
+    fn charge_limit_stays_in_range() {
+        let mut app = charging_app(50.0, 0.0);
+        for _ in 0..20 {
+            tap(&mut app, KeyCode::BracketRight);
+        }
+        assert_eq!(app.world().resource::<ChargeLimit>().0, 100.0);
+        for _ in 0..20 {
+            tap(&mut app, KeyCode::BracketLeft);
+        }
+        assert_eq!(app.world().resource::<ChargeLimit>().0, 50.0);
+    }
+
+    #[test]
+    fn power_tapers_when_full() {
+        assert_eq!(ChargerType::Ac.power(50.0), ChargerType::Ac.max_power());
+        assert!(ChargerType::Ac.power(99.0) < ChargerType::Ac.max_power());
+        assert_eq!(ChargerType::Dc.power(40.0), ChargerType::Dc.max_power());
+        assert!(ChargerType::Dc.power(80.0) < ChargerType::Dc.power(60.0));
+    }
+}
@@ -0,0 +1,426 @@
+use bevy::prelude::*;
+
+use crate::{
+    battery::{BATTERY_CAPACITY, BatteryLevel, SIMULATED_HOURS_PER_SECOND},
+    input::keyboard_backend,
+    theme::{DashboardTheme, Themed},
+    widgets::{Widget, widget_enabled, widget_turned_on},
+};
+
+const MIN_TEMPERATURE: f32 = 16.0;
+const MAX_TEMPERATURE: f32 = 28.0;
+const MAX_FAN_SPEED: u8 = 5;
+// Fraction of the difference with the outside lost per second, through the windows and body
+const CABIN_LEAK: f32 = 0.01;
+// Fraction of the difference with the target removed per second, for each fan step
+const FAN_EXCHANGE: f32 = 0.02;
+// Power in kW of each part of the climate system
+const FAN_POWER: f32 = 0.1;
+const HEATER_POWER: f32 = 1.0;
+const COMPRESSOR_POWER: f32 = 0.6;
+const WINDSHIELD_HEATER_POWER: f32 = 1.5;
+// Difference with the target in °C under which the heater and compressor slow down
+const TEMPERATURE_BAND: f32 = 2.0;
+
+pub fn climate_plugin(app: &mut App) {
+    app.add_plugins(climate_simulation_plugin).add_systems(
+        Update,
+        (
+            setup.run_if(widget_turned_on(Widget::Climate)),
+            display.run_if(widget_enabled(Widget::Climate)),
+        )
+            .chain()
+            .after(update_cabin),
+    );
+}
+
+/// Cabin temperature following the climate settings, drawing from the simulated `BatteryLevel`
+pub fn climate_simulation_plugin(app: &mut App) {
+    app.init_resource::<ClimateSettings>()
+        .init_resource::<CabinTemperature>()
+        .init_resource::<ClimatePower>()
+        .add_systems(
+            Update,
+            (
+                control_climate,
+                update_cabin,
+                drain_battery.run_if(keyboard_backend.and(resource_exists::<BatteryLevel>)),
+            )
+                .chain(),
+        );
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Zone {
+    Driver,
+    Passenger,
+}
+
+impl Zone {
+    const ALL: [Zone; 2] = [Zone::Driver, Zone::Passenger];
+
+    fn label(self) -> &'static str {
+        match self {
+            Zone::Driver => "Driver",
+            Zone::Passenger => "Passenger",
+        }
+    }
+}
+
+#[derive(Resource, Clone, PartialEq, Debug)]
+pub struct ClimateSettings {
+    /// Target temperature of each zone in °C
+    pub targets: [f32; 2],
+    /// 0 when the climate is off
+    pub fan_speed: u8,
+    pub air_conditioning: bool,
+    /// Clears the windshield with the fan at full speed, the A/C and the windshield heater
+    pub defrost: bool,
+}
+
+impl Default for ClimateSettings {
+    fn default() -> Self {
+        Self {
+            targets: [21.0, 21.0],
+            fan_speed: 2,
+            air_conditioning: true,
+            defrost: false,
+        }
+    }
+}
+
+impl ClimateSettings {
+    pub fn target(&self, zone: Zone) -> f32 {
+        self.targets[zone as usize]
+    }
+
+    fn effective_fan_speed(&self) -> u8 {
+        if self.defrost {
+            MAX_FAN_SPEED
+        } else {
+            self.fan_speed
+        }
+    }
+
+    fn cooling(&self) -> bool {
+        self.air_conditioning || self.defrost
+    }
+}
+
+/// Temperatures in °C
+#[derive(Resource, Clone, PartialEq, Debug)]
+pub struct CabinTemperature {
+    pub zones: [f32; 2],
+    pub outside: f32,
+}
+
+impl Default for CabinTemperature {
+    fn default() -> Self {
+        // A car parked in the summer sun
+        Self {
+            zones: [32.0, 32.0],
+            outside: 28.0,
+        }
+    }
+}
+
+impl CabinTemperature {
+    pub fn zone(&self, zone: Zone) -> f32 {
+        self.zones[zone as usize]
+    }
+}
+
+/// Power drawn by the climate system in kW
+#[derive(Resource, Default, PartialEq)]
+pub struct ClimatePower(pub f32);
+
+// T and G change the driver temperature, with Shift for the passenger, F and V the fan speed,
+// Y toggles the A/C and H the defrost
+fn control_climate(input: Res<ButtonInput<KeyCode>>, mut settings: ResMut<ClimateSettings>) {
+    let zone = if input.pressed(KeyCode::ShiftLeft) || input.pressed(KeyCode::ShiftRight) {
+        Zone::Passenger
+    } else {
+        Zone::Driver
+    };
+    let target = &mut settings.targets[zone as usize];
+    if input.just_pressed(KeyCode::KeyT) {
+        *target = (*target + 0.5).min(MAX_TEMPERATURE);
+    }
+    if input.just_pressed(KeyCode::KeyG) {
+        *target = (*target - 0.5).max(MIN_TEMPERATURE);
+    }
+    if input.just_pressed(KeyCode::KeyF) {
+        settings.fan_speed = (settings.fan_speed + 1).min(MAX_FAN_SPEED);
+    }
+    if input.just_pressed(KeyCode::KeyV) {
+        settings.fan_speed = settings.fan_speed.saturating_sub(1);
+    }
+    if input.just_pressed(KeyCode::KeyY) {
+        settings.air_conditioning = !settings.air_conditioning;
+    }
+    if input.just_pressed(KeyCode::KeyH) {
+        settings.defrost = !settings.defrost;
+    }
+}
+
+// Each zone drifts toward the outside temperature, and the fan blows air toward the target.
+// Without the A/C, the air can't be cooled below the outside temperature
+fn update_cabin(
+    settings: Res<ClimateSettings>,
+    mut cabin: ResMut<CabinTemperature>,
+    mut power: ResMut<ClimatePower>,
+    time: Res<Time>,
+) {
+    let delta = time.delta_secs();
+    let fan_speed = settings.effective_fan_speed() as f32;
+    let outside = cabin.outside;
+
+    let mut total_power = fan_speed * FAN_POWER;
+    if settings.defrost {
+        total_power += WINDSHIELD_HEATER_POWER;
+    }
+    for zone in Zone::ALL {
+        let temperature = &mut cabin.zones[zone as usize];
+        let mut air = settings.target(zone);
+        if !settings.cooling() {
+            air = air.max(outside);
+        }
+        let load = ((air - *temperature) / TEMPERATURE_BAND).clamp(-1.0, 1.0);
+        if load > 0.0 {
+            total_power += fan_speed * load * HEATER_POWER;
+        } else if settings.cooling() {
+            total_power -= fan_speed * load * COMPRESSOR_POWER;
+        }
+
+        let exchange = (fan_speed * FAN_EXCHANGE * delta).min(1.0);
+        *temperature += (air - *temperature) * exchange;
+        *temperature += (outside - *temperature) * (CABIN_LEAK * delta).min(1.0);
+    }
+    power.set_if_neq(ClimatePower(total_power));
+}
+
+fn drain_battery(power: Res<ClimatePower>, mut battery: ResMut<BatteryLevel>, time: Res<Time>) {
+    let energy = power.0 * time.delta_secs() * SIMULATED_HOURS_PER_SECOND;
+    battery.0 = (battery.0 - energy / BATTERY_CAPACITY * 100.0).max(0.0);
+}
+
+#[derive(Component)]
+struct ZoneTarget(Zone);
+
+#[derive(Component)]
+struct ZoneTemperature(Zone);
+
+#[derive(Component)]
+struct FanReadout;
+
+#[derive(Component)]
+struct PowerReadout;
+
+#[derive(Component, Clone, Copy)]
+enum ClimateToggle {
+    AirConditioning,
+    Defrost,
+}
+
+fn setup(mut commands: Commands) {
+    commands.spawn(Widget::Climate).with_children(|parent| {
+        for (zone, x) in Zone::ALL.into_iter().zip([-80.0, 80.0]) {
+            parent.spawn((
+                Text2d::new(zone.label()),
+                TextFont::from_font_size(16.0),
+                Transform::from_xyz(x, 60.0, 0.0),
+                Themed::SecondaryText,
+            ));
+            parent.spawn((
+                Text2d::new(""),
+                TextFont::from_font_size(36.0),
+                Transform::from_xyz(x, 25.0, 0.0),
+                Themed::Text,
+                ZoneTarget(zone),
+            ));
+            parent.spawn((
+                Text2d::new(""),
+                TextFont::from_font_size(14.0),
+                Transform::from_xyz(x, -5.0, 0.0),
+                Themed::SecondaryText,
+                ZoneTemperature(zone),
+            ));
+        }
+        parent.spawn((
+            Text2d::new(""),
+            TextFont::from_font_size(18.0),
+            Transform::from_xyz(0.0, -40.0, 0.0),
+            Themed::Text,
+            FanReadout,
+        ));
+        for (toggle, label, x) in [
+            (ClimateToggle::AirConditioning, "A/C", -80.0),
+            (ClimateToggle::Defrost, "DEFROST", 80.0),
+        ] {
+            parent.spawn((
+                Text2d::new(label),
+                TextFont::from_font_size(18.0),
+                Transform::from_xyz(x, -75.0, 0.0),
+                toggle,
+            ));
+        }
+        parent.spawn((
+            Text2d::new(""),
+            TextFont::from_font_size(14.0),
+            Transform::from_xyz(0.0, -105.0, 0.0),
+            Themed::SecondaryText,
+            PowerReadout,
+        ));
+    });
+}
+
+#[allow(clippy::type_complexity)]
+fn display(
+    settings: Res<ClimateSettings>,
+    cabin: Res<CabinTemperature>,
+    power: Res<ClimatePower>,
+    theme: Res<DashboardTheme>,
+    mut texts: Query<
+        (
+            &mut Text2d,
+            Option<&ZoneTarget>,
+            Option<&ZoneTemperature>,
+            Has<FanReadout>,
+        ),
+        Or<(
+            With<ZoneTarget>,
+            With<ZoneTemperature>,
+            With<FanReadout>,
+            With<PowerReadout>,
+        )>,
+    >,
+    mut toggles: Query<(Ref<ClimateToggle>, &mut TextColor)>,
+) {
+    for (mut text, target, temperature, fan) in &mut texts {
+        let added = text.is_added();
+        if let Some(ZoneTarget(zone)) = target {
+            if added || settings.is_changed() {
+                text.0 = format!("{:.1}°", settings.target(*zone));
+            }
+        } else if let Some(ZoneTemperature(zone)) = temperature {
+            if added || cabin.is_changed() {
+                text.0 = format!("Cabin {:.1}°C", cabin.zone(*zone));
+            }
+        } else if fan {
+            if added || settings.is_changed() {
+                let speed = settings.effective_fan_speed();
+                text.0 = format!(
+                    "Fan {}{}",
+                    "■".repeat(speed as usize),
+                    "□".repeat((MAX_FAN_SPEED - speed) as usize)
+                );
+            }
+        } else if added || power.is_changed() {
+            text.0 = format!("{:.1} kW", power.0);
+        }
+    }
+
+    for (toggle, mut color) in &mut toggles {
+        if !settings.is_changed() && !theme.is_changed() && !toggle.is_added() {
+            continue;
+        }
+        let on = match *toggle {
+            ClimateToggle::AirConditioning => settings.air_conditioning,
+            ClimateToggle::Defrost => settings.defrost,
+        };
+        color.0 = if on {
+            theme.palette.signal_on
+        } else {
+            theme.palette.signal_off
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{hold, run_for, tap, test_app};
+
+    fn climate_app(settings: ClimateSettings) -> App {
+        let mut app = test_app();
+        app.add_plugins(climate_simulation_plugin)
+            .insert_resource(settings)
+            .insert_resource(BatteryLevel(50.0));
+        app
+    }
+
+    fn cabin(app: &App, zone: Zone) -> f32 {
+        app.world().resource::<CabinTemperature>().zone(zone)
+    }
+
+    #[test]
+    fn cabin_reaches_each_zone_target() {
+        let mut app = climate_app(ClimateSettings {
+            targets: [20.0, 24.0],
+            fan_speed: 5,
+            ..default()
+        });
+        run_for(&mut app, 120.0);
+        assert!((cabin(&app, Zone::Driver) - 20.0).abs() < 1.5);
+        assert!((cabin(&app, Zone::Passenger) - 24.0).abs() < 1.5);
+        assert!(cabin(&app, Zone::Driver) < cabin(&app, Zone::Passenger));
+    }
+
+    #[test]
+    fn no_cooling_below_outside_without_air_conditioning() {
+        let mut app = climate_app(ClimateSettings {
+            targets: [18.0, 18.0],
+            fan_speed: 5,
+            air_conditioning: false,
+            ..default()
+        });
+        run_for(&mut app, 120.0);
+        let outside = app.world().resource::<CabinTemperature>().outside;
+        assert!(cabin(&app, Zone::Driver) >= outside - 0.01);
+    }
+
+    #[test]
+    fn cabin_drifts_to_outside_when_off() {
+        let mut app = climate_app(ClimateSettings {
+            fan_speed: 0,
+            ..default()
+        });
+        run_for(&mut app, 300.0);
+        let outside = app.world().resource::<CabinTemperature>().outside;
+        assert!((cabin(&app, Zone::Driver) - outside).abs() < 0.5);
+        assert_eq!(app.world().resource::<ClimatePower>().0, 0.0);
+    }
+
+    #[test]
+    fn air_conditioning_drains_the_battery() {
+        let level = |air_conditioning| {
+            let mut app = climate_app(ClimateSettings {
+                targets: [18.0, 18.0],
+                air_conditioning,
+                ..default()
+            });
+            run_for(&mut app, 10.0);
+            app.world().resource::<BatteryLevel>().0
+        };
+        assert!(level(true) < level(false));
+        assert!(level(false) < 50.0);
+    }
+
+    #[test]
+    fn keys_change_the_settings() {
+        let mut app = climate_app(ClimateSettings::default());
+        tap(&mut app, KeyCode::KeyT);
+        tap(&mut app, KeyCode::KeyF);
+        tap(&mut app, KeyCode::KeyY);
+        hold(&mut app, KeyCode::ShiftLeft);
+        tap(&mut app, KeyCode::KeyG);
+        tap(&mut app, KeyCode::KeyH);
+
+        let settings = app.world().resource::<ClimateSettings>();
+        assert_eq!(settings.targets, [21.5, 20.5]);
+        assert_eq!(settings.fan_speed, 3);
+        assert!(!settings.air_conditioning);
+        assert!(settings.defrost);
+        assert_eq!(settings.effective_fan_speed(), MAX_FAN_SPEED);
+    }
+}
@@ -0,0 +1,250 @@
+use bevy::prelude::*;
+
+use crate::{
+    battery::BatteryStatus,
+    gear::Gear,
+    input::keyboard_backend,
+    speed::{self, MAX_SPEED, Speed, SpeedUnit},
+    theme::{DashboardTheme, Themed},
+    widgets::{Widget, widget_enabled, widget_turned_on},
+};
+
+const MIN_CRUISE_SPEED: f32 = 20.0;
+// Proportional gain and acceleration limit in km/h/s when reaching the target speed
+const CRUISE_GAIN: f32 = 2.0;
+const CRUISE_ACCELERATION: f32 = 15.0;
+
+pub fn cruise_plugin(app: &mut App) {
+    app.add_plugins(cruise_simulation_plugin).add_systems(
+        Update,
+        (
+            setup.run_if(widget_turned_on(Widget::Cruise)),
+            display_cruise.run_if(widget_enabled(Widget::Cruise)),
+        )
+            .chain(),
+    );
+}
+
+/// Set speed held from the keyboard, on top of the speed simulation
+pub fn cruise_simulation_plugin(app: &mut App) {
+    app.init_resource::<CruiseControl>()
+        .add_systems(
+            Update,
+            (
+                control_cruise.run_if(in_state(Gear::Drive)),
+                hold_speed.after(speed::update_speed),
+            )
+                .chain()
+                .run_if(keyboard_backend.and(resource_exists::<Speed>)),
+        )
+        .add_systems(OnEnter(BatteryStatus::Charging), cancel_cruise)
+        .add_systems(OnExit(Gear::Drive), cancel_cruise);
+}
+
+#[derive(Resource, Default)]
+pub struct CruiseControl {
+    /// Speed in km/h to hold, kept when cancelled so that it can be resumed
+    pub target: Option<f32>,
+    pub engaged: bool,
+    /// Speed when the controller last ran, to know how much was lost since
+    held_speed: Option<f32>,
+}
+
+fn control_cruise(
+    input: Res<ButtonInput<KeyCode>>,
+    speed: Res<Speed>,
+    mut cruise: ResMut<CruiseControl>,
+) {
+    if input.just_pressed(KeyCode::KeyC) && speed.0 >= MIN_CRUISE_SPEED {
+        cruise.target = Some(speed.0.round());
+        cruise.engaged = true;
+    }
+    if input.just_pressed(KeyCode::KeyR) && cruise.target.is_some() {
+        cruise.engaged = true;
+    }
+    if input.just_pressed(KeyCode::KeyX) || input.pressed(KeyCode::ArrowDown) {
+        cruise.engaged = false;
+    }
+
+    let step = if input.pressed(KeyCode::ShiftLeft) || input.pressed(KeyCode::ShiftRight) {
+        5.0
+    } else {
+        1.0
+    };
+    let adjustment = if input.just_pressed(KeyCode::Equal) {
+        step
+    } else if input.just_pressed(KeyCode::Minus) {
+        -step
+    } else {
+        0.0
+    };
+    if adjustment != 0.0
+        && cruise.engaged
+        && let Some(target) = cruise.target
+    {
+        cruise.target = Some((target + adjustment).clamp(MIN_CRUISE_SPEED, MAX_SPEED));
+    }
+}
+
+fn hold_speed(mut cruise: ResMut<CruiseControl>, mut speed: ResMut<Speed>, time: Res<Time>) {
+    let (true, Some(target)) = (cruise.engaged, cruise.target) else {
+        cruise.bypass_change_detection().held_speed = None;
+        return;
+    };
+
+    // Give back what was lost to coasting since the last frame, but not what the driver added
+    if let Some(held_speed) = cruise.held_speed {
+        speed.0 += (held_speed - speed.0).max(0.0);
+    }
+    let error = target - speed.0;
+    speed.0 +=
+        (error * CRUISE_GAIN).clamp(-CRUISE_ACCELERATION, CRUISE_ACCELERATION) * time.delta_secs();
+    cruise.bypass_change_detection().held_speed = Some(speed.0);
+}
+
+fn cancel_cruise(mut cruise: ResMut<CruiseControl>) {
+    cruise.engaged = false;
+}
+
+#[derive(Component)]
+struct CruiseIndicator;
+
+#[derive(Component)]
+struct CruiseIcon;
+
+#[derive(Component)]
+struct CruiseSetSpeed;
+
+fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.spawn((
+        Widget::Cruise,
+        children![(
+            CruiseIndicator,
+            Visibility::Hidden,
+            Transform::default(),
+            children![
+                (
+                    Sprite::from_image(asset_server.load("signals/cruise.png")),
+                    CruiseIcon,
+                ),
+                (
+                    Text2d::new(""),
+                    TextFont::from_font_size(24.0),
+                    Transform::from_xyz(80.0, 0.0, 0.0),
+                    Themed::Text,
+                    CruiseSetSpeed,
+                ),
+            ],
+        )],
+    ));
+}
+
+fn display_cruise(
+    cruise: Res<CruiseControl>,
+    unit: Option<Res<SpeedUnit>>,
+    theme: Res<DashboardTheme>,
+    indicator: Single<(Ref<CruiseIndicator>, &mut Visibility)>,
+    mut icon: Single<&mut Sprite, With<CruiseIcon>>,
+    mut text: Single<&mut Text2d, With<CruiseSetSpeed>>,
+) {
+    let (marker, mut visibility) = indicator.into_inner();
+    let unit_changed = unit.as_ref().is_some_and(|unit| unit.is_changed());
+    if !cruise.is_changed() && !theme.is_changed() && !unit_changed && !marker.is_added() {
+        return;
+    }
+
+    let Some(target) = cruise.target else {
+        *visibility = Visibility::Hidden;
+        return;
+    };
+    *visibility = Visibility::Inherited;
+    let unit = unit.map_or(SpeedUnit::Kmh, |unit| *unit);
+    text.0 = format!("{:.0} {}", unit.convert(target), unit.speed_label());
+    icon.color = if cruise.engaged {
+        theme.palette.signal_on
+    } else {
+        theme.palette.secondary_text
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        speed::speed_simulation_plugin,
+        testing::{hold, release, run_for, set_state, tap, test_app},
+    };
+
+    fn cruise_app(speed: f32) -> App {
+        let mut app = test_app();
+        app.init_state::<BatteryStatus>()
+            .init_state::<Gear>()
+            .add_plugins((speed_simulation_plugin, cruise_simulation_plugin))
+            .insert_resource(Speed(speed));
+        app
+    }
+
+    fn cruise(app: &App) -> &CruiseControl {
+        app.world().resource::<CruiseControl>()
+    }
+
+    fn speed(app: &App) -> f32 {
+        app.world().resource::<Speed>().0
+    }
+
+    #[test]
+    fn set_speed_is_held_while_coasting() {
+        let mut app = cruise_app(80.0);
+        tap(&mut app, KeyCode::KeyC);
+        run_for(&mut app, 10.0);
+        assert!(cruise(&app).engaged);
+        assert!((speed(&app) - 80.0).abs() < 1.0);
+
+        // Not engaged below the minimum speed
+        let mut app = cruise_app(MIN_CRUISE_SPEED - 5.0);
+        tap(&mut app, KeyCode::KeyC);
+        assert_eq!(cruise(&app).target, None);
+    }
+
+    #[test]
+    fn set_speed_adjustments() {
+        let mut app = cruise_app(80.0);
+        tap(&mut app, KeyCode::KeyC);
+        tap(&mut app, KeyCode::Equal);
+        assert_eq!(cruise(&app).target, Some(81.0));
+        hold(&mut app, KeyCode::ShiftLeft);
+        tap(&mut app, KeyCode::Equal);
+        assert_eq!(cruise(&app).target, Some(86.0));
+        tap(&mut app, KeyCode::Minus);
+        assert_eq!(cruise(&app).target, Some(81.0));
+        release(&mut app, KeyCode::ShiftLeft);
+        tap(&mut app, KeyCode::Minus);
+        assert_eq!(cruise(&app).target, Some(80.0));
+
+        run_for(&mut app, 10.0);
+        assert!((speed(&app) - 80.0).abs() < 1.0);
+    }
+
+    #[test]
+    fn braking_cancels_and_resume_comes_back() {
+        let mut app = cruise_app(80.0);
+        tap(&mut app, KeyCode::KeyC);
+        tap(&mut app, KeyCode::ArrowDown);
+        assert!(!cruise(&app).engaged);
+        assert_eq!(cruise(&app).target, Some(80.0));
+
+        run_for(&mut app, 1.0);
+        assert!(speed(&app) < 60.0);
+        tap(&mut app, KeyCode::KeyR);
+        run_for(&mut app, 10.0);
+        assert!((speed(&app) - 80.0).abs() < 1.0);
+    }
+
+    #[test]
+    fn charging_cancels() {
+        let mut app = cruise_app(80.0);
+        tap(&mut app, KeyCode::KeyC);
+        set_state(&mut app, BatteryStatus::Charging);
+        assert!(!cruise(&app).engaged);
+    }
+}
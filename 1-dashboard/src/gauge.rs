@@ -0,0 +1,195 @@
+use bevy::{
+    prelude::*,
+    render::render_resource::{AsBindGroup, ShaderType},
+    shader::ShaderRef,
+    sprite_render::{AlphaMode2d, Material2d, Material2dPlugin},
+};
+
+const MAX_GRADIENT_STOPS: usize = 4;
+
+pub fn gauge_plugin(app: &mut App) {
+    app.add_plugins(Material2dPlugin::<GaugeMaterial>::default());
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum GaugeShape {
+    /// Filled from left to right along the mesh
+    Linear,
+    /// An arc on a square mesh, filled clockwise
+    Radial {
+        /// Angle in radians, clockwise from the top, in `-PI..PI`
+        start_angle: f32,
+        end_angle: f32,
+        /// Fraction of the outer radius
+        inner_radius: f32,
+    },
+}
+
+/// Bar or arc showing a value between `min` and `max`
+#[derive(Asset, TypePath, AsBindGroup, Clone, Debug)]
+#[uniform(0, GaugeUniform)]
+pub struct GaugeMaterial {
+    pub shape: GaugeShape,
+    pub value: f32,
+    pub min: f32,
+    pub max: f32,
+    /// Value from which the gauge is filled, `min` unless the gauge is centered on zero
+    pub origin: f32,
+    /// Fill colors with their position from 0 to 1, up to 4 stops. A single stop is a solid fill.
+    pub gradient: Vec<(f32, Color)>,
+    /// Color the whole fill from the gradient at the current value, instead of along the gauge
+    pub gradient_by_value: bool,
+    pub background: Color,
+    /// Number of intervals between tick marks, 0 for no ticks
+    pub ticks: u32,
+    pub tick_color: Color,
+    /// Number of segments lit one by one, 0 for a continuous gauge
+    pub segments: u32,
+}
+
+impl GaugeMaterial {
+    pub fn linear(color: Color) -> Self {
+        Self {
+            shape: GaugeShape::Linear,
+            value: 0.0,
+            min: 0.0,
+            max: 1.0,
+            origin: 0.0,
+            gradient: vec![(0.0, color)],
+            gradient_by_value: false,
+            background: Color::NONE,
+            ticks: 0,
+            tick_color: Color::WHITE,
+            segments: 0,
+        }
+    }
+
+    pub fn radial(start_angle: f32, end_angle: f32, inner_radius: f32, color: Color) -> Self {
+        Self {
+            shape: GaugeShape::Radial {
+                start_angle,
+                end_angle,
+                inner_radius,
+            },
+            ..Self::linear(color)
+        }
+    }
+
+    /// A range spanning zero is filled from zero, like a power and regen meter
+    pub fn with_range(mut self, min: f32, max: f32) -> Self {
+        self.min = min;
+        self.max = max;
+        self.origin = if min < 0.0 && max > 0.0 { 0.0 } else { min };
+        self
+    }
+
+    pub fn set_color(&mut self, color: Color) {
+        self.gradient = vec![(0.0, color)];
+    }
+
+    fn normalize(&self, value: f32) -> f32 {
+        if self.max == self.min {
+            return 0.0;
+        }
+        ((value - self.min) / (self.max - self.min)).clamp(0.0, 1.0)
+    }
+}
+
+#[derive(ShaderType)]
+struct GaugeUniform {
+    colors: [LinearRgba; MAX_GRADIENT_STOPS],
+    stops: Vec4,
+    background: LinearRgba,
+    tick_color: LinearRgba,
+    value: f32,
+    origin: f32,
+    stop_count: u32,
+    gradient_by_value: u32,
+    radial: u32,
+    start_angle: f32,
+    end_angle: f32,
+    inner_radius: f32,
+    ticks: u32,
+    segments: u32,
+}
+
+impl<'a> From<&'a GaugeMaterial> for GaugeUniform {
+    fn from(material: &'a GaugeMaterial) -> Self {
+        let mut colors = [LinearRgba::NONE; MAX_GRADIENT_STOPS];
+        let mut stops = Vec4::ZERO;
+        let stop_count = material.gradient.len().min(MAX_GRADIENT_STOPS);
+        for (i, (stop, color)) in material.gradient.iter().take(stop_count).enumerate() {
+            colors[i] = (*color).into();
+            stops[i] = *stop;
+        }
+        let (radial, start_angle, end_angle, inner_radius) = match material.shape {
+            GaugeShape::Linear => (0, 0.0, 0.0, 0.0),
+            GaugeShape::Radial {
+                start_angle,
+                end_angle,
+                inner_radius,
+            } => (1, start_angle, end_angle, inner_radius),
+        };
+        Self {
+            colors,
+            stops,
+            background: material.background.into(),
+            tick_color: material.tick_color.into(),
+            value: material.normalize(material.value),
+            origin: material.normalize(material.origin),
+            stop_count: stop_count as u32,
+            gradient_by_value: material.gradient_by_value as u32,
+            radial,
+            start_angle,
+            end_angle,
+            inner_radius,
+            ticks: material.ticks,
+            segments: material.segments,
+        }
+    }
+}
+
+impl Material2d for GaugeMaterial {
+    fn fragment_shader() -> ShaderRef {
+        "shaders/gauge.wgsl".into()
+    }
+
+    fn alpha_mode(&self) -> AlphaMode2d {
+        AlphaMode2d::Blend
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filled(material: &GaugeMaterial) -> (f32, f32) {
+        let uniform = GaugeUniform::from(material);
+        (
+            uniform.origin.min(uniform.value),
+            uniform.origin.max(uniform.value),
+        )
+    }
+
+    #[test]
+    fn filled_from_min() {
+        let mut material = GaugeMaterial::linear(Color::WHITE).with_range(0.0, 100.0);
+        material.value = 25.0;
+        assert_eq!(filled(&material), (0.0, 0.25));
+    }
+
+    #[test]
+    fn centered_on_zero() {
+        let mut material =
+            GaugeMaterial::radial(-1.0, 1.0, 0.75, Color::WHITE).with_range(-1.0, 1.0);
+        assert_eq!(material.origin, 0.0);
+        // Nothing is lit without power
+        let (start, end) = filled(&material);
+        assert_eq!(start, end);
+
+        material.value = -0.5;
+        assert_eq!(filled(&material), (0.25, 0.5));
+        material.value = 1.0;
+        assert_eq!(filled(&material), (0.5, 1.0));
+    }
+}
@@ -0,0 +1,181 @@
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    input::keyboard_backend,
+    speed::Speed,
+    theme::DashboardTheme,
+    widgets::{Widget, widget_enabled, widget_turned_on},
+};
+
+// Speed in km/h under which the car is considered stopped
+const STOPPED_SPEED: f32 = 1.0;
+const GEAR_SPACING: f32 = 40.0;
+
+pub fn gear_plugin(app: &mut App) {
+    app.init_state::<Gear>().add_systems(
+        Update,
+        (
+            shift.run_if(keyboard_backend),
+            setup.run_if(widget_turned_on(Widget::Gear)),
+            display_gear.run_if(widget_enabled(Widget::Gear)),
+        )
+            .chain(),
+    );
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, States, Default, Deserialize)]
+pub enum Gear {
+    Park,
+    Reverse,
+    Neutral,
+    #[default]
+    Drive,
+}
+
+impl Gear {
+    /// Positions on the selector lever
+    const LEVER: [Gear; 4] = [Gear::Park, Gear::Reverse, Gear::Neutral, Gear::Drive];
+
+    fn label(self) -> &'static str {
+        match self {
+            Gear::Park => "P",
+            Gear::Reverse => "R",
+            Gear::Neutral => "N",
+            Gear::Drive => "D",
+        }
+    }
+
+    /// Speed is in km/h, negative when going backward
+    fn can_engage(self, speed: f32) -> bool {
+        match self {
+            Gear::Park | Gear::Reverse => speed.abs() < STOPPED_SPEED,
+            Gear::Neutral => true,
+            // The car must stop before going forward when it is rolling backward
+            Gear::Drive => speed > -STOPPED_SPEED,
+        }
+    }
+}
+
+fn shift(
+    input: Res<ButtonInput<KeyCode>>,
+    gear: Res<State<Gear>>,
+    mut next_gear: ResMut<NextState<Gear>>,
+    speed: Option<Res<Speed>>,
+) {
+    let position = Gear::LEVER
+        .iter()
+        .position(|lever| lever == gear.get())
+        .unwrap();
+    let target = if input.just_pressed(KeyCode::PageUp) {
+        position.checked_sub(1)
+    } else if input.just_pressed(KeyCode::PageDown) {
+        Some(position + 1)
+    } else {
+        None
+    };
+    let Some(target) = target.and_then(|target| Gear::LEVER.get(target)) else {
+        return;
+    };
+
+    let speed = speed.map_or(0.0, |speed| speed.0);
+    if target.can_engage(speed) {
+        next_gear.set(*target);
+    } else {
+        info!("Can't shift to {target:?} at {speed:.0} km/h");
+    }
+}
+
+#[derive(Component)]
+struct GearLabel(Gear);
+
+fn setup(mut commands: Commands) {
+    commands.spawn(Widget::Gear).with_children(|parent| {
+        let offset = (Gear::LEVER.len() as f32 - 1.0) * GEAR_SPACING / 2.0;
+        for (i, gear) in Gear::LEVER.into_iter().enumerate() {
+            parent.spawn((
+                Text2d::new(gear.label()),
+                TextFont::from_font_size(32.0),
+                Transform::from_xyz(i as f32 * GEAR_SPACING - offset, 0.0, 0.0),
+                GearLabel(gear),
+            ));
+        }
+    });
+}
+
+fn display_gear(
+    gear: Res<State<Gear>>,
+    theme: Res<DashboardTheme>,
+    mut labels: Query<(Ref<GearLabel>, &mut TextColor)>,
+) {
+    for (label, mut color) in &mut labels {
+        if !gear.is_changed() && !theme.is_changed() && !label.is_added() {
+            continue;
+        }
+        color.0 = if label.0 == *gear.get() {
+            theme.palette.text
+        } else {
+            theme.palette.signal_off
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        battery::BatteryStatus,
+        speed::speed_simulation_plugin,
+        testing::{hold, run_for, set_state, tap, test_app},
+    };
+
+    fn gear_app(speed: f32) -> App {
+        let mut app = test_app();
+        app.init_state::<Gear>()
+            .init_state::<BatteryStatus>()
+            .add_systems(Update, shift)
+            .insert_resource(Speed(speed));
+        app
+    }
+
+    /// Move the lever, the gear is engaged on the next update
+    fn move_lever(app: &mut App, key: KeyCode) -> Gear {
+        tap(app, key);
+        app.update();
+        *app.world().resource::<State<Gear>>().get()
+    }
+
+    #[test]
+    fn park_and_reverse_are_refused_while_moving() {
+        let mut app = gear_app(30.0);
+        assert_eq!(move_lever(&mut app, KeyCode::PageUp), Gear::Neutral);
+        assert_eq!(move_lever(&mut app, KeyCode::PageUp), Gear::Neutral);
+
+        app.insert_resource(Speed(0.5));
+        assert_eq!(move_lever(&mut app, KeyCode::PageUp), Gear::Reverse);
+        assert_eq!(move_lever(&mut app, KeyCode::PageUp), Gear::Park);
+    }
+
+    #[test]
+    fn drive_is_refused_while_reversing() {
+        let mut app = gear_app(-10.0);
+        set_state(&mut app, Gear::Reverse);
+        assert_eq!(move_lever(&mut app, KeyCode::PageDown), Gear::Neutral);
+        assert_eq!(move_lever(&mut app, KeyCode::PageDown), Gear::Neutral);
+
+        // Rolling forward in neutral is fine
+        app.insert_resource(Speed(10.0));
+        assert_eq!(move_lever(&mut app, KeyCode::PageDown), Gear::Drive);
+    }
+
+    #[test]
+    fn reverse_throttle_goes_backward() {
+        let mut app = gear_app(0.0);
+        app.add_plugins(speed_simulation_plugin);
+        move_lever(&mut app, KeyCode::PageUp);
+        assert_eq!(move_lever(&mut app, KeyCode::PageUp), Gear::Reverse);
+        hold(&mut app, KeyCode::Space);
+        run_for(&mut app, 1.0);
+        assert!(app.world().resource::<Speed>().0 < -5.0);
+    }
+}
@@ -0,0 +1,257 @@
+use bevy::prelude::*;
+
+// Distances are in logical pixels on the screen
+const SWIPE_DISTANCE: f32 = 60.0;
+// A press that moves less than this is still a tap or a long press
+const TAP_TOLERANCE: f32 = 10.0;
+const LONG_PRESS_DURATION: f32 = 0.6;
+
+pub fn gesture_plugin(app: &mut App) {
+    app.add_systems(Update, detect_long_press)
+        .add_observer(start_gesture)
+        .add_observer(track_drag)
+        .add_observer(end_drag)
+        .add_observer(end_press)
+        .add_observer(cancel_press);
+}
+
+/// Detects swipes, taps and long presses from touch or mouse on this entity, triggering
+/// [`Swiped`], [`Tapped`] and [`LongPressed`]
+#[derive(Component, Default)]
+pub struct Gestures {
+    /// Time at which the current press started
+    pressed_at: Option<f32>,
+    moved: bool,
+    long_pressed: bool,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SwipeDirection {
+    Left,
+    Right,
+}
+
+#[derive(EntityEvent, Clone, Copy, Debug)]
+pub struct Swiped {
+    pub entity: Entity,
+    pub direction: SwipeDirection,
+}
+
+#[derive(EntityEvent, Clone, Copy, Debug)]
+pub struct Tapped {
+    pub entity: Entity,
+}
+
+#[derive(EntityEvent, Clone, Copy, Debug)]
+pub struct LongPressed {
+    pub entity: Entity,
+}
+
+fn start_gesture(press: On<Pointer<Press>>, mut gestures: Query<&mut Gestures>, time: Res<Time>) {
+    if let Ok(mut gestures) = gestures.get_mut(press.entity) {
+        *gestures = Gestures {
+            pressed_at: Some(time.elapsed_secs()),
+            ..default()
+        };
+    }
+}
+
+fn track_drag(drag: On<Pointer<Drag>>, mut gestures: Query<&mut Gestures>) {
+    if let Ok(mut gestures) = gestures.get_mut(drag.entity)
+        && drag.distance.length() > TAP_TOLERANCE
+    {
+        gestures.moved = true;
+    }
+}
+
+fn end_drag(
+    drag: On<Pointer<DragEnd>>,
+    mut gestures: Query<&mut Gestures>,
+    mut commands: Commands,
+) {
+    let Ok(mut gestures) = gestures.get_mut(drag.entity) else {
+        return;
+    };
+    gestures.pressed_at = None;
+
+    // Mostly horizontal, screen coordinates go right and down
+    let distance = drag.distance;
+    if distance.x.abs() >= SWIPE_DISTANCE && distance.x.abs() > distance.y.abs() {
+        commands.trigger(Swiped {
+            entity: drag.entity,
+            direction: if distance.x < 0.0 {
+                SwipeDirection::Left
+            } else {
+                SwipeDirection::Right
+            },
+        });
+    }
+}
+
+fn end_press(
+    release: On<Pointer<Release>>,
+    mut gestures: Query<&mut Gestures>,
+    mut commands: Commands,
+) {
+    let Ok(mut gestures) = gestures.get_mut(release.entity) else {
+        return;
+    };
+    if gestures.pressed_at.take().is_some() && !gestures.moved && !gestures.long_pressed {
+        commands.trigger(Tapped {
+            entity: release.entity,
+        });
+    }
+}
+
+fn cancel_press(cancel: On<Pointer<Cancel>>, mut gestures: Query<&mut Gestures>) {
+    if let Ok(mut gestures) = gestures.get_mut(cancel.entity) {
+        gestures.pressed_at = None;
+    }
+}
+
+// Long presses fire while still pressed, without waiting for the release
+fn detect_long_press(
+    mut gestures: Query<(Entity, &mut Gestures)>,
+    time: Res<Time>,
+    mut commands: Commands,
+) {
+    let now = time.elapsed_secs();
+    for (entity, mut gestures) in &mut gestures {
+        if let Some(pressed_at) = gestures.pressed_at
+            && !gestures.moved
+            && !gestures.long_pressed
+            && now - pressed_at >= LONG_PRESS_DURATION
+        {
+            gestures.long_pressed = true;
+            commands.trigger(LongPressed { entity });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fmt::Debug;
+
+    use bevy::{
+        camera::NormalizedRenderTarget,
+        picking::{
+            backend::HitData,
+            pointer::{Location, PointerButton, PointerId},
+        },
+    };
+
+    use super::*;
+    use crate::testing::{record_as, recorded, run_for, test_app};
+
+    fn gesture_app() -> (App, Entity) {
+        let mut app = test_app();
+        app.add_plugins(gesture_plugin);
+        record_as(&mut app, |swipe: &Swiped| {
+            format!("swipe {:?}", swipe.direction)
+        });
+        record_as(&mut app, |_: &Tapped| "tap".to_string());
+        record_as(&mut app, |_: &LongPressed| "long press".to_string());
+        let entity = app.world_mut().spawn(Gestures::default()).id();
+        (app, entity)
+    }
+
+    fn send<E: Debug + Clone + Reflect>(app: &mut App, entity: Entity, event: E) {
+        app.world_mut().trigger(Pointer {
+            entity,
+            pointer_id: PointerId::Mouse,
+            pointer_location: Location {
+                target: NormalizedRenderTarget::None {
+                    width: 1280,
+                    height: 720,
+                },
+                position: Vec2::ZERO,
+            },
+            event,
+        });
+        app.update();
+    }
+
+    fn hit() -> HitData {
+        HitData::new(Entity::PLACEHOLDER, 0.0, None, None)
+    }
+
+    fn press(app: &mut App, entity: Entity) {
+        let press = Press {
+            button: PointerButton::Primary,
+            hit: hit(),
+        };
+        send(app, entity, press);
+    }
+
+    fn release(app: &mut App, entity: Entity) {
+        let release = Release {
+            button: PointerButton::Primary,
+            hit: hit(),
+        };
+        send(app, entity, release);
+    }
+
+    fn drag(app: &mut App, entity: Entity, distance: Vec2) {
+        let drag = Drag {
+            button: PointerButton::Primary,
+            distance,
+            delta: distance,
+        };
+        send(app, entity, drag);
+        let drag_end = DragEnd {
+            button: PointerButton::Primary,
+            distance,
+        };
+        send(app, entity, drag_end);
+    }
+
+    fn recognized(app: &App) -> Vec<String> {
+        recorded::<String>(app).to_vec()
+    }
+
+    #[test]
+    fn quick_press_is_a_tap() {
+        let (mut app, entity) = gesture_app();
+        press(&mut app, entity);
+        run_for(&mut app, 0.2);
+        release(&mut app, entity);
+        assert_eq!(recognized(&app), ["tap"]);
+    }
+
+    #[test]
+    fn held_press_is_a_long_press() {
+        let (mut app, entity) = gesture_app();
+        press(&mut app, entity);
+        run_for(&mut app, 1.0);
+        assert_eq!(recognized(&app), ["long press"]);
+
+        // Releasing after a long press is not a tap
+        release(&mut app, entity);
+        assert_eq!(recognized(&app), ["long press"]);
+    }
+
+    #[test]
+    fn horizontal_drags_are_swipes() {
+        let (mut app, entity) = gesture_app();
+        press(&mut app, entity);
+        drag(&mut app, entity, vec2(-100.0, 20.0));
+        release(&mut app, entity);
+        press(&mut app, entity);
+        drag(&mut app, entity, vec2(100.0, -20.0));
+        release(&mut app, entity);
+        assert_eq!(recognized(&app), ["swipe Left", "swipe Right"]);
+    }
+
+    #[test]
+    fn short_or_vertical_drags_are_ignored() {
+        let (mut app, entity) = gesture_app();
+        press(&mut app, entity);
+        drag(&mut app, entity, vec2(30.0, 0.0));
+        release(&mut app, entity);
+        press(&mut app, entity);
+        drag(&mut app, entity, vec2(80.0, 120.0));
+        run_for(&mut app, 1.0);
+        release(&mut app, entity);
+        assert!(recognized(&app).is_empty());
+    }
+}
@@ -0,0 +1,89 @@
+use bevy::prelude::*;
+
+use crate::{
+    battery::{BatteryLevel, BatteryStatus},
+    speed::{MAX_SPEED, Speed},
+    turn::TurnSignal,
+};
+
+pub fn input_plugin(app: &mut App) {
+    app.init_resource::<InputBackend>()
+        .add_observer(apply_vehicle_data);
+}
+
+/// Where the vehicle data driving the dashboard comes from
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum InputBackend {
+    /// Keyboard controls, with the vehicle simulated by each widget
+    #[default]
+    Keyboard,
+    Can,
+    Replay,
+}
+
+/// Run condition: the vehicle is driven from the keyboard and simulated
+pub fn keyboard_backend(backend: Res<InputBackend>) -> bool {
+    *backend == InputBackend::Keyboard
+}
+
+/// Vehicle data received from a backend other than the keyboard
+#[derive(Event, Clone, Copy, Debug)]
+pub enum VehicleData {
+    /// Speed in km/h, negative when reversing
+    Speed(f32),
+    /// Battery state of charge in percent
+    StateOfCharge(f32),
+    Charging(bool),
+    TurnSignal(TurnSignal),
+}
+
+fn apply_vehicle_data(
+    data: On<VehicleData>,
+    mut commands: Commands,
+    speed: Option<ResMut<Speed>>,
+    battery: Option<ResMut<BatteryLevel>>,
+    battery_status: Res<State<BatteryStatus>>,
+    mut next_battery_status: ResMut<NextState<BatteryStatus>>,
+) {
+    match *data {
+        VehicleData::Speed(value) => {
+            if let Some(mut speed) = speed {
+                speed.0 = value.clamp(-MAX_SPEED, MAX_SPEED);
+            }
+        }
+        VehicleData::StateOfCharge(value) => {
+            if let Some(mut battery) = battery {
+                battery.0 = value.clamp(0.0, 100.0);
+            }
+        }
+        VehicleData::Charging(charging) => {
+            let status = if charging {
+                BatteryStatus::Charging
+            } else {
+                BatteryStatus::Unplugged
+            };
+            if *battery_status.get() != status {
+                next_battery_status.set(status);
+            }
+        }
+        VehicleData::TurnSignal(signal) => commands.trigger(signal),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::test_app;
+
+    #[test]
+    fn vehicle_speed_keeps_its_direction() {
+        let mut app = test_app();
+        app.init_state::<BatteryStatus>()
+            .add_plugins(input_plugin)
+            .insert_resource(Speed(0.0));
+        for (data, speed) in [(-12.0, -12.0), (250.0, MAX_SPEED), (-250.0, -MAX_SPEED)] {
+            app.world_mut().trigger(VehicleData::Speed(data));
+            assert_eq!(app.world().resource::<Speed>().0, speed);
+        }
+    }
+}
@@ -0,0 +1,242 @@
+use bevy::{
+    app::{HierarchyPropagatePlugin, Propagate, PropagateSet},
+    asset::{AssetLoader, LoadContext, io::Reader},
+    camera::{
+        RenderTarget,
+        visibility::{RenderLayers, VisibilitySystems},
+    },
+    picking::{PickingSystems, backend::PointerHits},
+    platform::collections::HashMap,
+    prelude::*,
+    window::{PrimaryWindow, WindowRef, WindowResized},
+};
+use serde::Deserialize;
+
+use crate::widgets::Widget;
+
+// Infotainment widgets and their camera are on this render layer, the cluster stays on the default one
+const INFOTAINMENT_LAYER: usize = 1;
+
+pub fn layout_plugin(app: &mut App) {
+    let second_window = std::env::args().any(|arg| arg == "--second-window");
+    app.init_asset::<DashboardLayout>()
+        .init_asset_loader::<LayoutLoader>()
+        .add_systems(
+            Startup,
+            (
+                open_infotainment_window.run_if(move || second_window),
+                setup,
+            )
+                .chain(),
+        )
+        .add_systems(Update, apply_layout)
+        .add_plugins(HierarchyPropagatePlugin::<RenderLayers>::new(PostUpdate))
+        .configure_sets(
+            PostUpdate,
+            PropagateSet::<RenderLayers>::default().before(VisibilitySystems::CheckVisibility),
+        )
+        .add_systems(
+            PreUpdate,
+            filter_hits_by_layer
+                .after(PickingSystems::Backend)
+                .before(PickingSystems::Hover)
+                .run_if(move || second_window),
+        );
+}
+
+/// Window in which a widget is shown, when the dashboard has a second window
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+enum DashboardWindow {
+    #[default]
+    Cluster,
+    Infotainment,
+}
+
+#[derive(Component)]
+struct InfotainmentWindow;
+
+#[derive(Asset, TypePath, Deserialize)]
+struct DashboardLayout {
+    /// Window size for which the positions are given, the layout is scaled to fit the actual window
+    reference_size: Vec2,
+    widgets: HashMap<Widget, WidgetPlacement>,
+}
+
+#[derive(Deserialize)]
+struct WidgetPlacement {
+    position: Vec2,
+    #[serde(default = "default_scale")]
+    scale: f32,
+    #[serde(default = "default_enabled")]
+    enabled: bool,
+    #[serde(default)]
+    window: DashboardWindow,
+}
+
+fn default_scale() -> f32 {
+    1.0
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+#[derive(Default, TypePath)]
+struct LayoutLoader;
+
+impl AssetLoader for LayoutLoader {
+    type Asset = DashboardLayout;
+    type Settings = ();
+    type Error = BevyError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        Ok(ron::de::from_bytes(&bytes)?)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["layout.ron"]
+    }
+}
+
+#[derive(Resource)]
+struct Layout(Handle<DashboardLayout>);
+
+fn setup(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    infotainment: Query<(), With<InfotainmentWindow>>,
+) {
+    let path = if infotainment.is_empty() {
+        "dashboard.layout.ron"
+    } else {
+        "dual.layout.ron"
+    };
+    commands.insert_resource(Layout(asset_server.load(path)));
+}
+
+fn open_infotainment_window(mut commands: Commands) {
+    let window = commands
+        .spawn((
+            Window {
+                title: "Infotainment".to_string(),
+                ..default()
+            },
+            InfotainmentWindow,
+        ))
+        .id();
+    commands.spawn((
+        Camera2d,
+        Camera {
+            target: RenderTarget::Window(WindowRef::Entity(window)),
+            ..default()
+        },
+        RenderLayers::layer(INFOTAINMENT_LAYER),
+    ));
+}
+
+// Sprite picking ignores render layers, so hits on widgets of the other window are dropped
+fn filter_hits_by_layer(mut hits: MessageMutator<PointerHits>, layers: Query<&RenderLayers>) {
+    let default_layers = RenderLayers::default();
+    let layers_of = |entity| layers.get(entity).unwrap_or(&default_layers);
+    for hit in hits.read() {
+        hit.picks
+            .retain(|(entity, data)| layers_of(*entity).intersects(layers_of(data.camera)));
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn apply_layout(
+    layout: Res<Layout>,
+    layouts: Res<Assets<DashboardLayout>>,
+    mut layout_events: MessageReader<AssetEvent<DashboardLayout>>,
+    mut resized: MessageReader<WindowResized>,
+    new_widgets: Query<(), Added<Widget>>,
+    primary_window: Single<&Window, With<PrimaryWindow>>,
+    infotainment_window: Query<&Window, With<InfotainmentWindow>>,
+    mut widgets: Query<(Entity, &Widget, &mut Transform, &mut Visibility)>,
+    mut commands: Commands,
+) {
+    let layout_changed = layout_events
+        .read()
+        .any(|event| event.is_loaded_with_dependencies(&layout.0) || event.is_modified(&layout.0));
+    let window_resized = resized.read().last().is_some();
+    if !layout_changed && !window_resized && new_widgets.is_empty() {
+        return;
+    }
+    let Some(layout) = layouts.get(&layout.0) else {
+        return;
+    };
+
+    let factor = |window: &Window| {
+        (window.width() / layout.reference_size.x).min(window.height() / layout.reference_size.y)
+    };
+
+    for (entity, widget, mut transform, mut visibility) in &mut widgets {
+        match layout.widgets.get(widget) {
+            Some(placement) if placement.enabled => {
+                // Without a second window, everything is shown in the cluster
+                let factor = match (placement.window, infotainment_window.single()) {
+                    (DashboardWindow::Infotainment, Ok(window)) => {
+                        commands
+                            .entity(entity)
+                            .insert(Propagate(RenderLayers::layer(INFOTAINMENT_LAYER)));
+                        factor(window)
+                    }
+                    _ => {
+                        commands.entity(entity).remove::<Propagate<RenderLayers>>();
+                        factor(&primary_window)
+                    }
+                };
+                transform.translation =
+                    (placement.position * factor).extend(transform.translation.z);
+                transform.scale = Vec3::splat(placement.scale * factor);
+                *visibility = Visibility::Visible;
+            }
+            _ => *visibility = Visibility::Hidden,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::picking::{backend::HitData, pointer::PointerId};
+
+    use super::*;
+    use crate::testing::test_app;
+
+    #[test]
+    fn hits_are_kept_to_the_window_of_the_widget() {
+        let mut app = test_app();
+        app.add_message::<PointerHits>()
+            .add_systems(Update, filter_hits_by_layer);
+        let world = app.world_mut();
+        let cluster_camera = world.spawn_empty().id();
+        let infotainment_camera = world.spawn(RenderLayers::layer(INFOTAINMENT_LAYER)).id();
+        let cluster_sprite = world.spawn_empty().id();
+        let infotainment_sprite = world.spawn(RenderLayers::layer(INFOTAINMENT_LAYER)).id();
+
+        for camera in [cluster_camera, infotainment_camera] {
+            let hit = HitData::new(camera, 0.0, None, None);
+            world.write_message(PointerHits::new(
+                PointerId::Mouse,
+                vec![(cluster_sprite, hit.clone()), (infotainment_sprite, hit)],
+                0.0,
+            ));
+        }
+        app.update();
+
+        let hits = app.world().resource::<Messages<PointerHits>>();
+        let picks: Vec<Vec<Entity>> = hits
+            .iter_current_update_messages()
+            .map(|hit| hit.picks.iter().map(|(entity, _)| *entity).collect())
+            .collect();
+        assert_eq!(picks, [vec![cluster_sprite], vec![infotainment_sprite]]);
+    }
+}
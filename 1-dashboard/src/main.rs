@@ -1,31 +1,80 @@
 use bevy::prelude::*;
 
-#[cfg(feature = "battery")]
+use crate::widgets::EnabledWidgets;
+
+mod analytics;
 mod battery;
-#[cfg(feature = "radio")]
+mod body;
+mod can;
+mod charging;
+mod climate;
+mod cruise;
+mod gauge;
+mod gear;
+mod gesture;
+mod input;
+mod layout;
 mod music;
-#[cfg(feature = "odometer")]
+mod navigation;
+mod notification;
 mod odometer;
-#[cfg(feature = "speedometer")]
+mod power;
+mod remote;
+mod road;
+mod scenario;
 mod speed;
+mod telemetry;
+mod telltale;
+#[cfg(test)]
+mod testing;
+mod theme;
+mod tpms;
 mod turn;
+mod widgets;
 
-fn main() {
-    App::new()
-        .add_plugins(DefaultPlugins.set(ImagePlugin::default_nearest()))
+fn main() -> AppExit {
+    let mut app = App::new();
+    if scenario::scenario_path().is_some() {
+        app.add_plugins(scenario::headless_plugins().set(ImagePlugin::default_nearest()));
+    } else {
+        app.add_plugins(DefaultPlugins.set(ImagePlugin::default_nearest()));
+    }
+    app.insert_resource(EnabledWidgets::from_args())
+        .add_plugins((
+            widgets::widgets_plugin,
+            input::input_plugin,
+            layout::layout_plugin,
+            theme::theme_plugin,
+            gauge::gauge_plugin,
+            gesture::gesture_plugin,
+            can::can_plugin,
+            telemetry::telemetry_plugin,
+            remote::remote_plugin,
+            scenario::scenario_plugin,
+        ))
         .add_plugins((
             turn::turn_plugin,
-            #[cfg(feature = "speedometer")]
             speed::speed_plugin,
-            #[cfg(feature = "battery")]
             battery::battery_plugin,
-            #[cfg(feature = "odometer")]
+            charging::charging_plugin,
             odometer::odometer_plugin,
-            #[cfg(feature = "radio")]
             music::music_plugin,
+            telltale::telltale_plugin,
+            road::road_plugin,
+            cruise::cruise_plugin,
+            gear::gear_plugin,
+            power::power_plugin,
+            navigation::navigation_plugin,
+        ))
+        .add_plugins((
+            notification::notification_plugin,
+            climate::climate_plugin,
+            tpms::tpms_plugin,
+            body::body_plugin,
+            analytics::analytics_plugin,
         ))
         .add_systems(Startup, setup)
-        .run();
+        .run()
 }
 
 fn setup(mut commands: Commands) {
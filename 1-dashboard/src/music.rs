@@ -1,10 +1,11 @@
-use bevy::{
-    color::palettes::tailwind,
-    prelude::*,
-    render::render_resource::{AsBindGroup, ShaderType},
-    shader::ShaderRef,
-    sprite::Anchor,
-    sprite_render::{Material2d, Material2dPlugin},
+use bevy::{prelude::*, sprite::Anchor, window::PrimaryWindow};
+
+use crate::{
+    gauge::GaugeMaterial,
+    gesture::{Gestures, LongPressed, SwipeDirection, Swiped, Tapped},
+    notification::call_active,
+    theme::{DashboardTheme, Themed},
+    widgets::{Widget, widget_enabled, widget_turned_on},
 };
 
 struct Song {
@@ -101,80 +102,142 @@ const SONG_LIST: [Song; 10] = [
 ];
 
 pub fn music_plugin(app: &mut App) {
-    app.add_plugins(Material2dPlugin::<ProgressMaterial>::default())
-        .add_systems(Startup, setup)
-        .add_systems(Update, (update, display))
-        .insert_resource(MusicState {
-            current_song: 1,
-            progress: 0.0,
-        });
+    app.add_plugins((MeshPickingPlugin, music_simulation_plugin))
+        .add_systems(
+            Update,
+            (
+                setup.run_if(widget_turned_on(Widget::Radio)),
+                (display, display_time, display_playlist, apply_theme)
+                    .run_if(widget_enabled(Widget::Radio)),
+            )
+                .chain(),
+        )
+        .add_observer(seek::<Press>)
+        .add_observer(seek::<Drag>)
+        .add_observer(change_song)
+        .add_observer(toggle_playback)
+        .add_observer(toggle_playlist);
 }
 
-#[derive(Asset, TypePath, AsBindGroup, ShaderType, Clone)]
-#[uniform(0, ProgressMaterial)]
-struct ProgressMaterial {
+/// Playback of the song list, which goes on while the radio is off
+pub fn music_simulation_plugin(app: &mut App) {
+    // The radio is ducked while a call rings or is ongoing
+    app.init_resource::<MusicState>()
+        .add_systems(Update, update.run_if(not(call_active)));
+}
+
+#[derive(Resource)]
+pub struct MusicState {
+    current_song: usize,
     progress: f32,
+    playing: bool,
 }
 
-impl<'a> From<&'a ProgressMaterial> for ProgressMaterial {
-    fn from(material: &'a ProgressMaterial) -> Self {
-        material.clone()
+impl Default for MusicState {
+    fn default() -> Self {
+        Self {
+            current_song: 1,
+            progress: 0.0,
+            playing: true,
+        }
     }
 }
 
-impl Material2d for ProgressMaterial {
-    fn fragment_shader() -> ShaderRef {
-        "shaders/music_progress.wgsl".into()
+impl MusicState {
+    /// Title and artist of the current song, like `Yesterday - The Beatles`
+    pub fn song(&self) -> String {
+        let song = &SONG_LIST[self.current_song];
+        format!("{} - {}", song.title, song.artist)
     }
-}
 
-#[derive(Resource)]
-struct MusicState {
-    current_song: usize,
-    progress: f32,
+    pub fn next_song(&mut self) {
+        self.current_song = (self.current_song + 1) % SONG_LIST.len();
+        self.progress = 0.0;
+    }
+
+    fn previous_song(&mut self) {
+        self.current_song = (self.current_song + SONG_LIST.len() - 1) % SONG_LIST.len();
+        self.progress = 0.0;
+    }
 }
 
 fn setup(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
     mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ProgressMaterial>>,
+    mut materials: ResMut<Assets<GaugeMaterial>>,
+    theme: Res<DashboardTheme>,
 ) {
     commands.spawn((
-        Transform::from_xyz(300.0, -50.0, 0.0),
-        Visibility::Visible,
+        Widget::Radio,
         children![
             (
                 Sprite::from_image(
                     asset_server.load("radio/sgt-peppers-lonely-hearts-club-band.png")
                 ),
                 Transform::from_xyz(0.0, 100.0, 0.0).with_scale(Vec3::splat(0.5)),
-                Artwork
+                Artwork,
+                Gestures::default(),
+            ),
+            (
+                Sprite::from_color(Color::BLACK.with_alpha(0.85), vec2(300.0, 260.0)),
+                Transform::from_xyz(0.0, 100.0, 1.0),
+                Visibility::Hidden,
+                // Presses go through to the artwork, to close it or control playback
+                Pickable::IGNORE,
+                Playlist,
+                children![(
+                    Text2d::new(""),
+                    TextFont::from_font_size(16.0),
+                    Transform::from_xyz(0.0, 0.0, 0.1),
+                    Themed::Text,
+                    PlaylistText,
+                )],
             ),
             (
                 Transform::from_xyz(-135.0, -50.0, 0.0),
                 Visibility::Visible,
-                SongInformation,
+                SongInformation::default(),
                 children![
                     (
                         Text2d::new(""),
                         Transform::from_xyz(0.0, 0.0, 0.0),
                         Anchor::CENTER_LEFT,
+                        Themed::Text,
                     ),
                     (
                         Text2d::new(""),
-                        TextColor(tailwind::GRAY_500.into()),
                         Transform::from_xyz(0.0, -25.0, 0.0),
                         Anchor::CENTER_LEFT,
+                        Themed::SecondaryText,
                     ),
                 ]
             ),
             (
-                Transform::from_xyz(0.0, -100.0, 0.0).with_scale(Vec3::new(270.0, 10.0, 0.0)),
+                Transform::from_xyz(0.0, -100.0, 0.0).with_scale(Vec3::new(270.0, 10.0, 1.0)),
                 Mesh2d(meshes.add(Rectangle::default())),
-                MeshMaterial2d(materials.add(ProgressMaterial { progress: 0.0 })),
+                MeshMaterial2d(materials.add(GaugeMaterial {
+                    background: theme.palette.progress_background,
+                    ..GaugeMaterial::linear(theme.palette.progress)
+                })),
                 ProgressIndicator,
             ),
+            (
+                Text2d::new(""),
+                TextFont::from_font_size(16.0),
+                Themed::SecondaryText,
+                Transform::from_xyz(-145.0, -100.0, 0.0),
+                Anchor::CENTER_RIGHT,
+                ElapsedTime,
+            ),
+            (
+                Text2d::new(""),
+                TextFont::from_font_size(16.0),
+                Themed::SecondaryText,
+                Transform::from_xyz(145.0, -100.0, 0.0),
+                Anchor::CENTER_LEFT,
+                RemainingTime,
+            ),
         ],
     ));
 }
@@ -183,41 +246,285 @@ fn setup(
 struct ProgressIndicator;
 
 #[derive(Component)]
-struct SongInformation;
+struct ElapsedTime;
+
+#[derive(Component)]
+struct RemainingTime;
+
+/// Title and artist of the song, shown again by a radio turned back on
+#[derive(Component, Default)]
+struct SongInformation {
+    displayed_song: Option<usize>,
+}
 
 #[derive(Component)]
 struct Artwork;
 
+#[derive(Component)]
+struct Playlist;
+
+#[derive(Component)]
+struct PlaylistText;
+
 fn update(mut current: ResMut<MusicState>, time: Res<Time>) {
+    if !current.playing {
+        return;
+    }
     current.progress += time.delta_secs() * 5.0;
     if current.progress > SONG_LIST[current.current_song].duration {
-        current.current_song = (current.current_song + 1) % SONG_LIST.len();
-        current.progress = 0.0;
+        current.next_song();
     }
 }
 
 #[allow(clippy::too_many_arguments)]
 fn display(
     current: Res<MusicState>,
-    material: Single<&MeshMaterial2d<ProgressMaterial>, With<ProgressIndicator>>,
-    mut progress_materials: ResMut<Assets<ProgressMaterial>>,
+    material: Single<&MeshMaterial2d<GaugeMaterial>, With<ProgressIndicator>>,
+    mut gauge_materials: ResMut<Assets<GaugeMaterial>>,
     mut artwork: Single<&mut Sprite, With<Artwork>>,
-    song_information: Single<&Children, With<SongInformation>>,
+    song_information: Single<(&mut SongInformation, &Children)>,
     mut text: Query<&mut Text2d>,
     asset_server: Res<AssetServer>,
-    mut last_displayed_song: Local<usize>,
 ) {
     let song = &SONG_LIST[current.current_song];
 
     let progress = current.progress / song.duration;
-    progress_materials.get_mut(*material).unwrap().progress = progress;
+    gauge_materials.get_mut(*material).unwrap().value = progress;
 
-    if *last_displayed_song != current.current_song {
-        text.get_mut(song_information[0]).unwrap().0 = song.title.to_string();
-        text.get_mut(song_information[1]).unwrap().0 = format!("{} - {}", song.artist, song.album);
+    let (mut information, children) = song_information.into_inner();
+    if information.displayed_song != Some(current.current_song) {
+        text.get_mut(children[0]).unwrap().0 = song.title.to_string();
+        text.get_mut(children[1]).unwrap().0 = format!("{} - {}", song.artist, song.album);
 
         artwork.image = asset_server.load(format!("radio/{}", song.artwork));
 
-        *last_displayed_song = current.current_song;
+        information.displayed_song = Some(current.current_song);
+    }
+}
+
+fn apply_theme(
+    theme: Res<DashboardTheme>,
+    material: Single<&MeshMaterial2d<GaugeMaterial>, With<ProgressIndicator>>,
+    mut gauge_materials: ResMut<Assets<GaugeMaterial>>,
+) {
+    if theme.is_changed() {
+        let material = gauge_materials.get_mut(*material).unwrap();
+        material.set_color(theme.palette.progress);
+        material.background = theme.palette.progress_background;
+    }
+}
+
+fn display_playlist(current: Res<MusicState>, mut text: Single<&mut Text2d, With<PlaylistText>>) {
+    if current.is_changed() {
+        text.0 = SONG_LIST
+            .iter()
+            .enumerate()
+            .map(|(index, song)| {
+                let marker = if index == current.current_song {
+                    ">"
+                } else {
+                    " "
+                };
+                format!("{marker} {}", song.title)
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+    }
+}
+
+// Swiping left on the artwork goes to the next song, like turning a page
+fn change_song(
+    swipe: On<Swiped>,
+    artwork: Query<(), With<Artwork>>,
+    current: Option<ResMut<MusicState>>,
+) {
+    let (Ok(()), Some(mut current)) = (artwork.get(swipe.entity), current) else {
+        return;
+    };
+    match swipe.direction {
+        SwipeDirection::Left => current.next_song(),
+        SwipeDirection::Right => current.previous_song(),
+    }
+}
+
+fn toggle_playback(
+    tap: On<Tapped>,
+    artwork: Query<(), With<Artwork>>,
+    current: Option<ResMut<MusicState>>,
+) {
+    let (Ok(()), Some(mut current)) = (artwork.get(tap.entity), current) else {
+        return;
+    };
+    current.playing = !current.playing;
+}
+
+fn toggle_playlist(
+    long_press: On<LongPressed>,
+    artwork: Query<(), With<Artwork>>,
+    mut playlist: Query<&mut Visibility, With<Playlist>>,
+) {
+    if artwork.contains(long_press.entity)
+        && let Ok(mut visibility) = playlist.single_mut()
+    {
+        *visibility = match *visibility {
+            Visibility::Hidden => Visibility::Inherited,
+            _ => Visibility::Hidden,
+        };
+    }
+}
+
+fn format_time(seconds: f32) -> String {
+    let seconds = seconds as u32;
+    format!("{:0>2}:{:0>2}", seconds / 60, seconds % 60)
+}
+
+fn display_time(
+    current: Res<MusicState>,
+    mut elapsed: Single<&mut Text2d, With<ElapsedTime>>,
+    mut remaining: Single<&mut Text2d, (With<RemainingTime>, Without<ElapsedTime>)>,
+) {
+    if current.is_changed() {
+        let song = &SONG_LIST[current.current_song];
+
+        elapsed.0 = format_time(current.progress);
+        remaining.0 = format!(
+            "-{}",
+            format_time((song.duration - current.progress).max(0.0).ceil())
+        );
+    }
+}
+
+// Clicking or dragging on the progress bar seeks in the current song
+fn seek<E: std::fmt::Debug + Clone + Reflect>(
+    event: On<Pointer<E>>,
+    bar: Query<&GlobalTransform, With<ProgressIndicator>>,
+    cameras: Query<(&Camera, &GlobalTransform)>,
+    primary_window: Query<Entity, With<PrimaryWindow>>,
+    current: Option<ResMut<MusicState>>,
+) {
+    let (Ok(bar), Some(mut current)) = (bar.get(event.entity), current) else {
+        return;
+    };
+    // With a second window, use the camera of the window the pointer is in
+    let Some((camera, camera_transform)) = cameras.iter().find(|(camera, _)| {
+        camera
+            .target
+            .normalize(primary_window.single().ok())
+            .as_ref()
+            == Some(&event.pointer_location.target)
+    }) else {
+        return;
+    };
+    let Ok(position) =
+        camera.viewport_to_world_2d(camera_transform, event.pointer_location.position)
+    else {
+        return;
+    };
+
+    // The bar is a unit rectangle scaled to its size, so its local x goes from -0.5 to 0.5
+    let local = bar
+        .affine()
+        .inverse()
+        .transform_point3(position.extend(0.0));
+    let song = &SONG_LIST[current.current_song];
+    current.progress = (local.x + 0.5).clamp(0.0, 1.0) * song.duration;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        notification::{Notification, NotificationKind, Priority, notification_queue_plugin},
+        testing::{run_for, test_app},
+    };
+
+    fn music_app(current_song: usize, progress: f32) -> App {
+        let mut app = test_app();
+        app.add_plugins(music_simulation_plugin)
+            .insert_resource(MusicState {
+                current_song,
+                progress,
+                playing: true,
+            });
+        app
+    }
+
+    #[test]
+    fn progress_advances_five_times_faster() {
+        let mut app = music_app(0, 0.0);
+        run_for(&mut app, 2.0);
+        let state = app.world().resource::<MusicState>();
+        assert_eq!(state.current_song, 0);
+        assert!((state.progress - 10.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn next_song_at_the_end() {
+        let mut app = music_app(0, SONG_LIST[0].duration - 1.0);
+        run_for(&mut app, 1.0);
+        let state = app.world().resource::<MusicState>();
+        assert_eq!(state.current_song, 1);
+        assert!(state.progress < 5.0);
+    }
+
+    #[test]
+    fn wraps_to_the_first_song() {
+        let last = SONG_LIST.len() - 1;
+        let mut app = music_app(last, SONG_LIST[last].duration);
+        app.update();
+        let state = app.world().resource::<MusicState>();
+        assert_eq!(state.current_song, 0);
+        assert_eq!(state.progress, 0.0);
+    }
+
+    #[test]
+    fn paused_song_does_not_progress() {
+        let mut app = music_app(0, 10.0);
+        app.world_mut().resource_mut::<MusicState>().playing = false;
+        run_for(&mut app, 1.0);
+        assert_eq!(app.world().resource::<MusicState>().progress, 10.0);
+    }
+
+    #[test]
+    fn calls_pause_the_song() {
+        let mut app = music_app(0, 10.0);
+        app.add_plugins(notification_queue_plugin);
+        app.world_mut().trigger(Notification {
+            kind: NotificationKind::Call,
+            title: "Call".to_string(),
+            body: String::new(),
+            priority: Priority::High,
+            timeout: 2.0,
+        });
+        app.update();
+        let progress = app.world().resource::<MusicState>().progress;
+        run_for(&mut app, 1.0);
+        assert_eq!(app.world().resource::<MusicState>().progress, progress);
+
+        // Once the call stops ringing, the song continues
+        run_for(&mut app, 2.0);
+        assert!(app.world().resource::<MusicState>().progress > progress);
+    }
+
+    #[test]
+    fn skip_songs_both_ways() {
+        let mut state = MusicState {
+            current_song: 0,
+            progress: 42.0,
+            playing: true,
+        };
+        state.previous_song();
+        assert_eq!(state.current_song, SONG_LIST.len() - 1);
+        assert_eq!(state.progress, 0.0);
+        state.next_song();
+        state.next_song();
+        assert_eq!(state.current_song, 1);
+    }
+
+    #[test]
+    fn time_format() {
+        assert_eq!(format_time(0.0), "00:00");
+        assert_eq!(format_time(59.9), "00:59");
+        assert_eq!(format_time(228.0), "03:48");
     }
 }
@@ -0,0 +1,470 @@
+use bevy::{
+    asset::{AssetLoader, LoadContext, io::Reader},
+    prelude::*,
+};
+use serde::Deserialize;
+
+use crate::{
+    odometer::Distance,
+    speed::SpeedUnit,
+    theme::{DashboardTheme, Themed},
+    turn::TurnSignal,
+    widgets::{Widget, widget_enabled, widget_turned_on},
+};
+
+const EARTH_RADIUS: f64 = 6371.0;
+// Smaller changes of direction are just bends in the road
+const MIN_TURN_ANGLE: f32 = std::f32::consts::FRAC_PI_6;
+// Distance in km before a turn at which the turn signal is suggested
+const TURN_SUGGESTION_DISTANCE: f32 = 0.5;
+const MAP_SIZE: f32 = 200.0;
+
+pub fn navigation_plugin(app: &mut App) {
+    app.init_asset::<Route>()
+        .init_asset_loader::<RouteLoader>()
+        .init_asset_loader::<GpxLoader>()
+        .add_systems(Startup, load_route)
+        .add_systems(
+            Update,
+            (
+                setup.run_if(widget_turned_on(Widget::Navigation)),
+                (suggest_turns, display_maneuver, draw_map)
+                    .run_if(widget_enabled(Widget::Navigation)),
+            )
+                .chain(),
+        );
+}
+
+/// Suggestion for the turn signals from the navigation
+#[derive(Event, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TurnSuggestion {
+    /// A turn on this side is coming up
+    Upcoming(TurnSignal),
+    /// The turn on this side was taken
+    Completed(TurnSignal),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum ManeuverKind {
+    Left,
+    Right,
+    Arrive,
+}
+
+impl ManeuverKind {
+    fn label(self) -> &'static str {
+        match self {
+            ManeuverKind::Left => "Turn left",
+            ManeuverKind::Right => "Turn right",
+            ManeuverKind::Arrive => "Destination",
+        }
+    }
+
+    fn signal(self) -> Option<TurnSignal> {
+        match self {
+            ManeuverKind::Left => Some(TurnSignal::Left),
+            ManeuverKind::Right => Some(TurnSignal::Right),
+            ManeuverKind::Arrive => None,
+        }
+    }
+}
+
+struct Maneuver {
+    kind: ManeuverKind,
+    /// Distance in km along the route
+    at: f32,
+}
+
+/// Route to follow, as a polyline in km with x to the east and y to the north
+#[derive(Asset, TypePath)]
+struct Route {
+    points: Vec<Vec2>,
+    /// Distance in km along the route to each point
+    distances: Vec<f32>,
+    maneuvers: Vec<Maneuver>,
+}
+
+impl Route {
+    fn new(points: Vec<Vec2>) -> Result<Self, BevyError> {
+        if points.len() < 2 {
+            return Err("a route needs at least two points".into());
+        }
+
+        let mut distances = vec![0.0];
+        for segment in points.windows(2) {
+            distances.push(distances.last().unwrap() + segment[0].distance(segment[1]));
+        }
+
+        let mut maneuvers: Vec<Maneuver> = points
+            .windows(3)
+            .zip(&distances[1..])
+            .filter_map(|(corner, at)| {
+                let angle = (corner[1] - corner[0]).angle_to(corner[2] - corner[1]);
+                let kind = if angle > MIN_TURN_ANGLE {
+                    ManeuverKind::Left
+                } else if angle < -MIN_TURN_ANGLE {
+                    ManeuverKind::Right
+                } else {
+                    return None;
+                };
+                Some(Maneuver { kind, at: *at })
+            })
+            .collect();
+        maneuvers.push(Maneuver {
+            kind: ManeuverKind::Arrive,
+            at: *distances.last().unwrap(),
+        });
+
+        Ok(Self {
+            points,
+            distances,
+            maneuvers,
+        })
+    }
+
+    fn length(&self) -> f32 {
+        *self.distances.last().unwrap()
+    }
+
+    /// Index of the next maneuver after this distance along the route
+    fn next_maneuver(&self, distance: f32) -> Option<usize> {
+        self.maneuvers
+            .iter()
+            .position(|maneuver| maneuver.at > distance)
+    }
+
+    /// Position on the route after this distance, and the index of the last point passed
+    fn position_at(&self, distance: f32) -> (Vec2, usize) {
+        let distance = distance.clamp(0.0, self.length());
+        let index = self
+            .distances
+            .partition_point(|from| *from <= distance)
+            .clamp(1, self.points.len() - 1)
+            - 1;
+        let segment_length = self.distances[index + 1] - self.distances[index];
+        let factor = if segment_length > 0.0 {
+            (distance - self.distances[index]) / segment_length
+        } else {
+            0.0
+        };
+        (
+            self.points[index].lerp(self.points[index + 1], factor),
+            index,
+        )
+    }
+}
+
+#[derive(Deserialize)]
+struct RouteFile {
+    points: Vec<Vec2>,
+}
+
+#[derive(Default, TypePath)]
+struct RouteLoader;
+
+impl AssetLoader for RouteLoader {
+    type Asset = Route;
+    type Settings = ();
+    type Error = BevyError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let file: RouteFile = ron::de::from_bytes(&bytes)?;
+        Route::new(file.points)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["route.ron"]
+    }
+}
+
+/// Loads the track or route points of a GPX file, projected around the first point
+#[derive(Default, TypePath)]
+struct GpxLoader;
+
+impl AssetLoader for GpxLoader {
+    type Asset = Route;
+    type Settings = ();
+    type Error = BevyError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let text = String::from_utf8(bytes)?;
+
+        let coordinates = gpx_points(&text);
+        let Some(&(origin_lat, origin_lon)) = coordinates.first() else {
+            return Err("no track or route points in GPX file".into());
+        };
+        let points = coordinates
+            .iter()
+            .map(|(lat, lon)| {
+                vec2(
+                    ((lon - origin_lon).to_radians() * origin_lat.to_radians().cos() * EARTH_RADIUS)
+                        as f32,
+                    ((lat - origin_lat).to_radians() * EARTH_RADIUS) as f32,
+                )
+            })
+            .collect();
+        Route::new(points)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["gpx"]
+    }
+}
+
+/// Latitude and longitude of the `trkpt` and `rtept` elements, in the order of the file
+fn gpx_points(text: &str) -> Vec<(f64, f64)> {
+    text.split('<')
+        .filter(|element| {
+            let mut tag = element.split(|c: char| c.is_whitespace() || c == '>' || c == '/');
+            matches!(tag.next(), Some("trkpt" | "rtept"))
+        })
+        .filter_map(|element| Some((attribute(element, "lat")?, attribute(element, "lon")?)))
+        .collect()
+}
+
+/// Number in an attribute of an element's start tag, given without its leading `<`
+fn attribute(element: &str, name: &str) -> Option<f64> {
+    let tag = element.split('>').next()?;
+    // Skip the element name
+    let mut attributes = tag.trim_start_matches(|c: char| !c.is_whitespace());
+    loop {
+        let (key, rest) = attributes.split_once('=')?;
+        let rest = rest.trim_start();
+        let quote = rest.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+        let (value, rest) = rest[1..].split_once(quote)?;
+        if key.trim() == name {
+            return value.trim().parse().ok();
+        }
+        attributes = rest;
+    }
+}
+
+#[derive(Resource)]
+struct CurrentRoute(Handle<Route>);
+
+/// The route is `assets/commute.route.ron`, or the asset given with `--route <file>`
+fn load_route(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let args: Vec<String> = std::env::args().collect();
+    let path = args
+        .windows(2)
+        .find(|arg| arg[0] == "--route")
+        .map_or("commute.route.ron".to_string(), |arg| arg[1].clone());
+    commands.insert_resource(CurrentRoute(asset_server.load(path)));
+}
+
+#[derive(Component)]
+struct ManeuverText;
+
+#[derive(Component)]
+struct MiniMap;
+
+fn setup(mut commands: Commands) {
+    commands.spawn((
+        Widget::Navigation,
+        children![
+            (
+                Text2d::new(""),
+                TextFont::from_font_size(24.0),
+                Transform::from_xyz(0.0, -MAP_SIZE / 2.0 - 25.0, 0.0),
+                Themed::Text,
+                ManeuverText,
+            ),
+            (Transform::default(), MiniMap),
+        ],
+    ));
+}
+
+fn suggest_turns(
+    mut commands: Commands,
+    route: Res<CurrentRoute>,
+    routes: Res<Assets<Route>>,
+    distance: Option<Res<Distance>>,
+    mut suggested: Local<Option<usize>>,
+) {
+    let Some(route) = routes.get(&route.0) else {
+        return;
+    };
+    let distance = distance.map_or(0.0, |distance| distance.0);
+    let next = route.next_maneuver(distance);
+
+    if let Some(index) = *suggested
+        && next != Some(index)
+    {
+        if let Some(signal) = route
+            .maneuvers
+            .get(index)
+            .and_then(|maneuver| maneuver.kind.signal())
+        {
+            commands.trigger(TurnSuggestion::Completed(signal));
+        }
+        *suggested = None;
+    }
+
+    if let Some(index) = next
+        && *suggested != Some(index)
+        && route.maneuvers[index].at - distance <= TURN_SUGGESTION_DISTANCE
+        && let Some(signal) = route.maneuvers[index].kind.signal()
+    {
+        commands.trigger(TurnSuggestion::Upcoming(signal));
+        *suggested = Some(index);
+    }
+}
+
+fn display_maneuver(
+    mut text: Single<&mut Text2d, With<ManeuverText>>,
+    route: Res<CurrentRoute>,
+    routes: Res<Assets<Route>>,
+    distance: Option<Res<Distance>>,
+    unit: Option<Res<SpeedUnit>>,
+) {
+    let distance_changed = distance
+        .as_ref()
+        .is_some_and(|distance| distance.is_changed());
+    let unit_changed = unit.as_ref().is_some_and(|unit| unit.is_changed());
+    if !(text.is_added()
+        || route.is_changed()
+        || routes.is_changed()
+        || distance_changed
+        || unit_changed)
+    {
+        return;
+    }
+
+    let Some(route) = routes.get(&route.0) else {
+        text.0 = "No route".to_string();
+        return;
+    };
+    let distance = distance.map_or(0.0, |distance| distance.0);
+    let unit = unit.map_or(SpeedUnit::Kmh, |unit| *unit);
+
+    text.0 = match route.next_maneuver(distance) {
+        Some(index) => {
+            let maneuver = &route.maneuvers[index];
+            format!(
+                "{} in {:.1} {}",
+                maneuver.kind.label(),
+                unit.convert(maneuver.at - distance),
+                unit.distance_label()
+            )
+        }
+        None => "You have arrived".to_string(),
+    };
+}
+
+fn draw_map(
+    mut gizmos: Gizmos,
+    route: Res<CurrentRoute>,
+    routes: Res<Assets<Route>>,
+    distance: Option<Res<Distance>>,
+    map: Single<(&GlobalTransform, &InheritedVisibility), With<MiniMap>>,
+    theme: Res<DashboardTheme>,
+) {
+    let (transform, visibility) = *map;
+    let Some(route) = routes.get(&route.0) else {
+        return;
+    };
+    if !visibility.get() {
+        return;
+    }
+
+    let to_world = |point: Vec2| transform.transform_point(point.extend(0.0)).truncate();
+    let half = MAP_SIZE / 2.0;
+    gizmos.linestrip_2d(
+        [
+            vec2(-half, -half),
+            vec2(half, -half),
+            vec2(half, half),
+            vec2(-half, half),
+            vec2(-half, -half),
+        ]
+        .map(to_world),
+        theme.palette.secondary_text,
+    );
+
+    // Fit the whole route in the map, keeping its proportions
+    let bounds = Rect::from_corners(
+        route.points.iter().copied().reduce(Vec2::min).unwrap(),
+        route.points.iter().copied().reduce(Vec2::max).unwrap(),
+    );
+    let scale = 0.9 * MAP_SIZE / bounds.size().max_element().max(f32::EPSILON);
+    let to_map = |point: Vec2| to_world((point - bounds.center()) * scale);
+
+    gizmos.linestrip_2d(
+        route.points.iter().copied().map(to_map),
+        theme.palette.signal_off,
+    );
+    let (position, index) = route.position_at(distance.map_or(0.0, |distance| distance.0));
+    gizmos.linestrip_2d(
+        route.points[..=index]
+            .iter()
+            .copied()
+            .chain([position])
+            .map(to_map),
+        theme.palette.progress,
+    );
+    gizmos.circle_2d(to_map(position), 6.0, theme.palette.signal_on);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn attributes_in_any_order_and_quoting() {
+        assert_eq!(
+            attribute(r#"trkpt lat="48.5" lon="2.25">"#, "lat"),
+            Some(48.5)
+        );
+        assert_eq!(
+            attribute(r#"trkpt lon="2.25" lat="48.5">"#, "lat"),
+            Some(48.5)
+        );
+        assert_eq!(
+            attribute("trkpt lat='48.5' lon='2.25'/>", "lon"),
+            Some(2.25)
+        );
+        assert_eq!(
+            attribute("trkpt\n    lat = \" 48.5 \"\n    lon=\"2.25\">", "lat"),
+            Some(48.5)
+        );
+    }
+
+    #[test]
+    fn attributes_matched_by_whole_name() {
+        let element = r#"trkpt xlat="1.0" name="lat=3" lat="48.5">"#;
+        assert_eq!(attribute(element, "lat"), Some(48.5));
+        // Attributes of the content are not read
+        assert_eq!(attribute(r#"trkpt lat="48.5">lon="2.25""#, "lon"), None);
+        assert_eq!(attribute(r#"trkpt lat=48.5>"#, "lat"), None);
+        assert_eq!(attribute(r#"trkpt lat="north">"#, "lat"), None);
+    }
+
+    #[test]
+    fn track_and_route_points() {
+        let gpx = r#"<?xml version="1.0"?>
+            <gpx version="1.1">
+              <trk><trkseg>
+                <trkpt lat="48.0" lon="2.0"><ele>35</ele></trkpt>
+                <trkpt lon="2.1" lat="48.1"/>
+                <trkpt lat="48.2"/>
+              </trkseg></trk>
+              <rte><rtept lat='48.3' lon='2.3'></rtept></rte>
+              <trkpts lat="0" lon="0"/>
+            </gpx>"#;
+        assert_eq!(gpx_points(gpx), [(48.0, 2.0), (48.1, 2.1), (48.3, 2.3)]);
+    }
+}
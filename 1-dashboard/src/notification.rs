@@ -0,0 +1,542 @@
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader},
+    thread,
+    time::Duration,
+};
+
+use bevy::prelude::*;
+use crossbeam::channel::{self, Receiver, Sender};
+use serde::Deserialize;
+
+use crate::{
+    telltale::{Severity, Warning, WarningEvent},
+    theme::Themed,
+    widgets::{Widget, widget_enabled, widget_turned_on},
+};
+
+const BANNER_SIZE: Vec2 = vec2(520.0, 110.0);
+const BUTTON_SIZE: Vec2 = vec2(110.0, 36.0);
+// How often a notification file is checked for new lines once its end is reached
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+pub fn notification_plugin(app: &mut App) {
+    app.add_plugins(notification_queue_plugin)
+        .add_systems(
+            Update,
+            (
+                setup.run_if(widget_turned_on(Widget::Notifications)),
+                display.run_if(widget_enabled(Widget::Notifications)),
+            )
+                .chain()
+                .after(update_queue),
+        )
+        .add_observer(click_button);
+}
+
+/// Queue of notifications and their answers, without the banner
+pub fn notification_queue_plugin(app: &mut App) {
+    app.init_resource::<Notifications>()
+        .add_systems(Startup, start_reader)
+        .add_systems(
+            Update,
+            (
+                receive_notifications.run_if(resource_exists::<IncomingNotifications>),
+                answer_with_keys,
+                update_queue,
+            )
+                .chain(),
+        )
+        .add_observer(enqueue)
+        .add_observer(apply_action);
+}
+
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum NotificationKind {
+    /// Incoming phone call, ringing until accepted, declined or timed out
+    Call,
+    Message,
+    Alert,
+}
+
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, Default)]
+pub enum Priority {
+    Low,
+    #[default]
+    Normal,
+    High,
+}
+
+/// Notification to show in the banner, queued behind those with the same or a higher priority
+#[derive(Event, Deserialize, Clone, PartialEq, Debug)]
+pub struct Notification {
+    pub kind: NotificationKind,
+    pub title: String,
+    #[serde(default)]
+    pub body: String,
+    #[serde(default)]
+    pub priority: Priority,
+    /// Seconds the banner is shown without an answer
+    #[serde(default = "default_timeout")]
+    pub timeout: f32,
+}
+
+fn default_timeout() -> f32 {
+    8.0
+}
+
+/// Answer to the notification in the banner
+#[derive(Event, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum NotificationAction {
+    /// Accepts a call, or dismisses a message or alert
+    Accept,
+    /// Declines or hangs up a call, or dismisses a message or alert
+    Decline,
+}
+
+struct ShownNotification {
+    notification: Notification,
+    remaining: f32,
+    /// Duration of the call once it is accepted
+    call_duration: Option<f32>,
+}
+
+#[derive(Resource, Default)]
+pub struct Notifications {
+    current: Option<ShownNotification>,
+    pending: Vec<Notification>,
+}
+
+impl Notifications {
+    pub fn current(&self) -> Option<&Notification> {
+        self.current.as_ref().map(|shown| &shown.notification)
+    }
+
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    fn in_call(&self) -> bool {
+        self.current
+            .as_ref()
+            .is_some_and(|shown| shown.call_duration.is_some())
+    }
+
+    fn push(&mut self, notification: Notification) {
+        // After the last one with the same or a higher priority
+        let index = self
+            .pending
+            .iter()
+            .position(|pending| pending.priority < notification.priority)
+            .unwrap_or(self.pending.len());
+        self.pending.insert(index, notification);
+    }
+
+    fn show_next(&mut self) {
+        self.current = (!self.pending.is_empty()).then(|| {
+            let notification = self.pending.remove(0);
+            ShownNotification {
+                remaining: notification.timeout,
+                notification,
+                call_duration: None,
+            }
+        });
+    }
+}
+
+/// Run condition: a call is ringing or ongoing, so the radio is ducked
+pub fn call_active(notifications: Option<Res<Notifications>>) -> bool {
+    notifications.is_some_and(|notifications| {
+        notifications
+            .current()
+            .is_some_and(|notification| notification.kind == NotificationKind::Call)
+    })
+}
+
+// A higher priority notification replaces the current one, unless a call is ongoing
+fn enqueue(notification: On<Notification>, mut notifications: ResMut<Notifications>) {
+    let notification = notification.event().clone();
+    if let Some(current) = &notifications.current
+        && notification.priority > current.notification.priority
+        && !notifications.in_call()
+    {
+        let current = notifications.current.take().unwrap().notification;
+        notifications.pending.insert(0, current);
+    }
+    notifications.push(notification);
+}
+
+fn update_queue(mut notifications: ResMut<Notifications>, time: Res<Time>) {
+    let delta = time.delta_secs();
+    if let Some(shown) = &mut notifications.current {
+        match &mut shown.call_duration {
+            Some(duration) => *duration += delta,
+            None => shown.remaining -= delta,
+        }
+        if shown.remaining <= 0.0 {
+            notifications.current = None;
+        }
+    }
+    if notifications.current.is_none() {
+        notifications.show_next();
+    }
+}
+
+fn apply_action(action: On<NotificationAction>, mut notifications: ResMut<Notifications>) {
+    let Some(shown) = &mut notifications.current else {
+        return;
+    };
+    if *action.event() == NotificationAction::Accept
+        && shown.notification.kind == NotificationKind::Call
+        && shown.call_duration.is_none()
+    {
+        shown.call_duration = Some(0.0);
+    } else {
+        notifications.current = None;
+    }
+}
+
+fn answer_with_keys(input: Res<ButtonInput<KeyCode>>, mut commands: Commands) {
+    if input.just_pressed(KeyCode::KeyA) {
+        commands.trigger(NotificationAction::Accept);
+    }
+    if input.just_pressed(KeyCode::KeyD) {
+        commands.trigger(NotificationAction::Decline);
+    }
+}
+
+/// Source of injected notifications, selected with `--notifications <file>` or
+/// `--notification-socket <path>`, with one notification in RON on each line
+#[derive(Clone, Debug)]
+enum NotificationSource {
+    /// A file or named pipe, followed like `tail -f`
+    File(String),
+    /// A Unix socket accepting any number of connections
+    Socket(String),
+}
+
+impl NotificationSource {
+    fn from_args() -> Option<Self> {
+        let args: Vec<String> = std::env::args().collect();
+        args.windows(2).find_map(|arg| match arg[0].as_str() {
+            "--notifications" => Some(NotificationSource::File(arg[1].clone())),
+            "--notification-socket" => Some(NotificationSource::Socket(arg[1].clone())),
+            _ => None,
+        })
+    }
+}
+
+#[derive(Resource)]
+struct IncomingNotifications(Receiver<Notification>);
+
+fn start_reader(mut commands: Commands) {
+    let Some(source) = NotificationSource::from_args() else {
+        return;
+    };
+    let (sender, receiver) = channel::unbounded();
+    let started = match source.clone() {
+        NotificationSource::File(path) => File::open(&path).map(|file| {
+            thread::spawn(move || follow_file(BufReader::new(file), sender));
+        }),
+        NotificationSource::Socket(path) => listen(&path, sender),
+    };
+
+    match started {
+        Ok(()) => {
+            info!("Reading notifications from {source:?}");
+            commands.insert_resource(IncomingNotifications(receiver));
+        }
+        Err(error) => {
+            error!("Couldn't read notifications from {source:?}: {error}");
+            commands.trigger(WarningEvent::Raise(Warning::SystemFault));
+        }
+    }
+}
+
+fn follow_file(mut file: impl BufRead, sender: Sender<Notification>) {
+    let mut line = String::new();
+    loop {
+        line.clear();
+        match file.read_line(&mut line) {
+            Ok(0) => thread::sleep(POLL_INTERVAL),
+            Ok(_) => {
+                if !send_line(&line, &sender) {
+                    return;
+                }
+            }
+            Err(error) => {
+                error!("Couldn't read notifications: {error}");
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(unix)]
+fn listen(path: &str, sender: Sender<Notification>) -> io::Result<()> {
+    use std::os::unix::net::UnixListener;
+
+    // A socket left over by a previous run can't be bound again
+    let _ = std::fs::remove_file(path);
+    let listener = UnixListener::bind(path)?;
+    thread::spawn(move || {
+        for stream in listener.incoming().map_while(Result::ok) {
+            let sender = sender.clone();
+            thread::spawn(move || {
+                for line in BufReader::new(stream).lines().map_while(Result::ok) {
+                    if !send_line(&line, &sender) {
+                        return;
+                    }
+                }
+            });
+        }
+    });
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn listen(_path: &str, _sender: Sender<Notification>) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "notification sockets are only supported on Unix",
+    ))
+}
+
+/// Parses and sends a line, returning false once the dashboard is closed
+fn send_line(line: &str, sender: &Sender<Notification>) -> bool {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with("//") {
+        return true;
+    }
+    match ron::from_str(line) {
+        Ok(notification) => sender.send(notification).is_ok(),
+        Err(error) => {
+            warn!("Invalid notification {line}: {error}");
+            true
+        }
+    }
+}
+
+fn receive_notifications(incoming: Res<IncomingNotifications>, mut commands: Commands) {
+    for notification in incoming.0.try_iter() {
+        commands.trigger(notification);
+    }
+}
+
+#[derive(Component)]
+struct Banner;
+
+#[derive(Component)]
+struct BannerTitle;
+
+#[derive(Component)]
+struct BannerBody;
+
+#[derive(Component, Clone, Copy)]
+struct BannerButton(NotificationAction);
+
+fn setup(mut commands: Commands) {
+    let button = |action: NotificationAction, x: f32| {
+        (
+            Sprite::from_color(Color::BLACK, BUTTON_SIZE),
+            Transform::from_xyz(x, -BANNER_SIZE.y / 2.0 + 28.0, 0.1),
+            BannerButton(action),
+            children![(
+                Text2d::new(""),
+                TextFont::from_font_size(18.0),
+                Transform::from_xyz(0.0, 0.0, 0.1),
+                Themed::Text,
+            )],
+        )
+    };
+
+    commands.spawn((
+        Widget::Notifications,
+        // Above the other widgets, like an overlay
+        Transform::from_xyz(0.0, 0.0, 10.0),
+        children![(
+            Sprite::from_color(Color::BLACK.with_alpha(0.9), BANNER_SIZE),
+            Visibility::Hidden,
+            Banner,
+            children![
+                (
+                    Text2d::new(""),
+                    TextFont::from_font_size(22.0),
+                    Transform::from_xyz(0.0, 30.0, 0.1),
+                    Themed::Text,
+                    BannerTitle,
+                ),
+                (
+                    Text2d::new(""),
+                    TextFont::from_font_size(16.0),
+                    Transform::from_xyz(0.0, 5.0, 0.1),
+                    Themed::SecondaryText,
+                    BannerBody,
+                ),
+                button(NotificationAction::Accept, -70.0),
+                button(NotificationAction::Decline, 70.0),
+            ],
+        )],
+    ));
+}
+
+fn display(
+    notifications: Res<Notifications>,
+    mut banner: Single<(&mut Visibility, &mut Sprite), With<Banner>>,
+    mut title: Single<&mut Text2d, (With<BannerTitle>, Without<BannerBody>)>,
+    mut body: Single<&mut Text2d, (With<BannerBody>, Without<BannerTitle>)>,
+    mut buttons: Query<(&BannerButton, &mut Visibility, &mut Sprite, &Children), Without<Banner>>,
+    mut labels: Query<&mut Text2d, (Without<BannerTitle>, Without<BannerBody>)>,
+) {
+    if !notifications.is_changed() {
+        return;
+    }
+    let (visibility, sprite) = &mut *banner;
+    let Some(shown) = &notifications.current else {
+        **visibility = Visibility::Hidden;
+        return;
+    };
+    **visibility = Visibility::Inherited;
+
+    let notification = &shown.notification;
+    let color = match notification.kind {
+        NotificationKind::Call => Color::srgb(0.1, 0.6, 0.2),
+        NotificationKind::Message => Color::srgb(0.2, 0.3, 0.8),
+        NotificationKind::Alert => Severity::Caution.color(),
+    };
+    sprite.color = color.darker(0.3).with_alpha(0.9);
+
+    title.0 = notification.title.clone();
+    body.0 = match shown.call_duration {
+        Some(duration) => format!("{}:{:02}", duration as u32 / 60, duration as u32 % 60),
+        None => notification.body.clone(),
+    };
+    if notifications.pending() > 0 {
+        body.0 += &format!("  (+{} more)", notifications.pending());
+    }
+
+    let ringing = notification.kind == NotificationKind::Call && shown.call_duration.is_none();
+    for (button, mut visibility, mut sprite, children) in &mut buttons {
+        let label = match (button.0, notification.kind) {
+            (NotificationAction::Accept, NotificationKind::Call) if ringing => Some("Accept"),
+            (NotificationAction::Accept, _) => None,
+            (NotificationAction::Decline, NotificationKind::Call) if ringing => Some("Decline"),
+            (NotificationAction::Decline, NotificationKind::Call) => Some("Hang up"),
+            (NotificationAction::Decline, _) => Some("Dismiss"),
+        };
+        *visibility = if label.is_some() {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+        sprite.color = match button.0 {
+            NotificationAction::Accept => Color::srgb(0.1, 0.6, 0.2),
+            NotificationAction::Decline => Color::srgb(0.7, 0.1, 0.1),
+        };
+        labels.get_mut(children[0]).unwrap().0 = label.unwrap_or_default().to_string();
+    }
+}
+
+fn click_button(click: On<Pointer<Click>>, buttons: Query<&BannerButton>, mut commands: Commands) {
+    if let Ok(button) = buttons.get(click.entity) {
+        commands.trigger(button.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{run_for, tap, test_app};
+
+    fn notification_app() -> App {
+        let mut app = test_app();
+        app.add_plugins(notification_queue_plugin);
+        app
+    }
+
+    fn notify(app: &mut App, kind: NotificationKind, title: &str, priority: Priority) {
+        app.world_mut().trigger(Notification {
+            kind,
+            title: title.to_string(),
+            body: String::new(),
+            priority,
+            timeout: default_timeout(),
+        });
+        app.update();
+    }
+
+    fn current_title(app: &App) -> Option<String> {
+        app.world()
+            .resource::<Notifications>()
+            .current()
+            .map(|notification| notification.title.clone())
+    }
+
+    #[test]
+    fn notifications_are_shown_by_priority() {
+        let mut app = notification_app();
+        notify(
+            &mut app,
+            NotificationKind::Message,
+            "first",
+            Priority::Normal,
+        );
+        notify(&mut app, NotificationKind::Message, "low", Priority::Low);
+        notify(
+            &mut app,
+            NotificationKind::Message,
+            "second",
+            Priority::Normal,
+        );
+        assert_eq!(current_title(&app).as_deref(), Some("first"));
+
+        tap(&mut app, KeyCode::KeyD);
+        assert_eq!(current_title(&app).as_deref(), Some("second"));
+        tap(&mut app, KeyCode::KeyD);
+        assert_eq!(current_title(&app).as_deref(), Some("low"));
+    }
+
+    #[test]
+    fn higher_priority_interrupts_the_current_notification() {
+        let mut app = notification_app();
+        notify(
+            &mut app,
+            NotificationKind::Message,
+            "message",
+            Priority::Normal,
+        );
+        notify(&mut app, NotificationKind::Alert, "alert", Priority::High);
+        assert_eq!(current_title(&app).as_deref(), Some("alert"));
+
+        tap(&mut app, KeyCode::KeyA);
+        assert_eq!(current_title(&app).as_deref(), Some("message"));
+    }
+
+    #[test]
+    fn unanswered_notifications_time_out() {
+        let mut app = notification_app();
+        notify(
+            &mut app,
+            NotificationKind::Message,
+            "message",
+            Priority::Normal,
+        );
+        run_for(&mut app, default_timeout() + 0.5);
+        assert_eq!(current_title(&app), None);
+    }
+
+    #[test]
+    fn accepted_calls_last_until_hung_up() {
+        let mut app = notification_app();
+        notify(&mut app, NotificationKind::Call, "call", Priority::Normal);
+        tap(&mut app, KeyCode::KeyA);
+        notify(&mut app, NotificationKind::Alert, "alert", Priority::High);
+        run_for(&mut app, default_timeout() + 0.5);
+        assert_eq!(current_title(&app).as_deref(), Some("call"));
+        assert!(app.world_mut().run_system_cached(call_active).unwrap());
+
+        tap(&mut app, KeyCode::KeyD);
+        assert_eq!(current_title(&app).as_deref(), Some("alert"));
+        assert!(!app.world_mut().run_system_cached(call_active).unwrap());
+    }
+}
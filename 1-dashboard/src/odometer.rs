@@ -1,33 +1,95 @@
 use bevy::prelude::*;
 
-use crate::speed::Speed;
+use crate::{
+    speed::{Speed, SpeedUnit},
+    theme::Themed,
+    widgets::{Widget, widget_enabled, widget_turned_on},
+};
 
 pub fn odometer_plugin(app: &mut App) {
-    app.add_systems(Startup, setup)
-        .add_systems(Update, (update, display))
-        .insert_resource(Distance(0.0));
+    app.add_plugins(odometer_simulation_plugin).add_systems(
+        Update,
+        (
+            setup.run_if(widget_turned_on(Widget::Odometer)),
+            display.run_if(widget_enabled(Widget::Odometer)),
+        )
+            .chain(),
+    );
+}
+
+/// Distance accumulated from the speed, kept while the odometer is off
+pub fn odometer_simulation_plugin(app: &mut App) {
+    app.init_resource::<Distance>().add_systems(Update, update);
 }
 
 fn setup(mut commands: Commands) {
-    commands.spawn((
-        Transform::from_xyz(-300.0, -200.0, 0.0),
-        Text2d::default(),
-        Odometer,
-    ));
+    commands.spawn((Widget::Odometer, Text2d::default(), Themed::Text, Odometer));
 }
 
 #[derive(Component)]
 struct Odometer;
 
-#[derive(Resource)]
-struct Distance(f32);
+/// Distance travelled in km
+#[derive(Resource, Default)]
+pub struct Distance(pub f32);
 
-fn display(mut text: Single<&mut Text2d, With<Odometer>>, distance: Res<Distance>) {
-    if distance.is_changed() {
-        text.0 = format!("odometer: {:>5.1}km", distance.0);
+fn display(
+    mut text: Single<&mut Text2d, With<Odometer>>,
+    distance: Res<Distance>,
+    unit: Option<Res<SpeedUnit>>,
+) {
+    let unit_changed = unit.as_ref().is_some_and(|unit| unit.is_changed());
+    let unit = unit.map_or(SpeedUnit::Kmh, |unit| *unit);
+    if distance.is_changed() || unit_changed {
+        text.0 = format!(
+            "odometer: {:>5.1}{}",
+            unit.convert(distance.0),
+            unit.distance_label()
+        );
     }
 }
 
-fn update(mut distance: ResMut<Distance>, time: Res<Time>, speed: Res<Speed>) {
-    distance.0 += speed.0 / 60.0 * time.delta_secs();
+fn update(mut distance: ResMut<Distance>, time: Res<Time>, speed: Option<Res<Speed>>) {
+    let Some(speed) = speed else {
+        return;
+    };
+    // Distance travelled backward counts too
+    distance.0 += speed.0.abs() / 60.0 * time.delta_secs();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{run_for, test_app};
+
+    fn odometer_app(speed: f32) -> App {
+        let mut app = test_app();
+        app.add_plugins(odometer_simulation_plugin)
+            .insert_resource(Distance(0.0))
+            .insert_resource(Speed(speed));
+        app
+    }
+
+    #[test]
+    fn distance_follows_speed() {
+        // The simulation runs one minute per second
+        let mut app = odometer_app(60.0);
+        run_for(&mut app, 2.0);
+        assert!((app.world().resource::<Distance>().0 - 2.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn reversing_adds_distance() {
+        let mut app = odometer_app(-12.0);
+        run_for(&mut app, 5.0);
+        assert!((app.world().resource::<Distance>().0 - 1.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn no_distance_without_speed() {
+        let mut app = odometer_app(0.0);
+        app.world_mut().remove_resource::<Speed>();
+        run_for(&mut app, 1.0);
+        assert_eq!(app.world().resource::<Distance>().0, 0.0);
+    }
 }
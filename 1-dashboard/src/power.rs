@@ -0,0 +1,226 @@
+use bevy::{color::palettes, prelude::*};
+
+use crate::{
+    gauge::GaugeMaterial,
+    speed::Speed,
+    theme::{DashboardTheme, Themed},
+    widgets::{Widget, widget_enabled, widget_turned_on},
+};
+
+const AIR_DENSITY: f32 = 1.225;
+const GRAVITY: f32 = 9.81;
+const POWER_SMOOTHING: f32 = 8.0;
+const PEAK_HOLD_DURATION: f32 = 1.5;
+// Decay of the peak markers, as a fraction of the meter per second
+const PEAK_DECAY: f32 = 0.3;
+const METER_RADIUS: f32 = 80.0;
+// Angles of the ends of the arc, clockwise from the top
+const METER_ANGLE: f32 = 2.1;
+
+pub fn power_plugin(app: &mut App) {
+    app.init_resource::<VehicleModel>()
+        .init_resource::<Power>()
+        .add_systems(
+            Update,
+            (
+                update_power,
+                setup.run_if(widget_turned_on(Widget::PowerMeter)),
+                (display_power, apply_theme).run_if(widget_enabled(Widget::PowerMeter)),
+            )
+                .chain(),
+        );
+}
+
+/// Parameters used to estimate the power from the speed
+#[derive(Resource)]
+pub struct VehicleModel {
+    /// Mass in kg
+    pub mass: f32,
+    /// Drag coefficient times frontal area, in m²
+    pub drag_area: f32,
+    pub rolling_resistance: f32,
+    /// Maximum power of the motor in kW
+    pub max_power: f32,
+    /// Maximum regenerative braking power in kW, any more braking is done by the brakes
+    pub max_regen: f32,
+}
+
+impl Default for VehicleModel {
+    fn default() -> Self {
+        Self {
+            mass: 1800.0,
+            drag_area: 0.6,
+            rolling_resistance: 0.01,
+            max_power: 150.0,
+            max_regen: 60.0,
+        }
+    }
+}
+
+impl VehicleModel {
+    /// Power in kW for a velocity in m/s and an acceleration in m/s², negative when regenerating
+    fn power(&self, velocity: f32, acceleration: f32) -> f32 {
+        let drag = 0.5 * AIR_DENSITY * self.drag_area * velocity * velocity;
+        let rolling = self.rolling_resistance * self.mass * GRAVITY;
+        let force = self.mass * acceleration + (drag + rolling) * velocity.signum();
+        (force * velocity / 1000.0).clamp(-self.max_regen, self.max_power)
+    }
+
+    /// Position on a center-zero meter, from -1 for full regen to 1 for full power
+    fn meter_position(&self, power: f32) -> f32 {
+        if power >= 0.0 {
+            power / self.max_power
+        } else {
+            power / self.max_regen
+        }
+    }
+}
+
+/// Instant power in kW, negative while regenerating
+#[derive(Resource, Default, PartialEq)]
+pub struct Power(pub f32);
+
+fn update_power(
+    speed: Option<Res<Speed>>,
+    model: Res<VehicleModel>,
+    mut power: ResMut<Power>,
+    time: Res<Time>,
+    mut last_speed: Local<Option<f32>>,
+) {
+    let delta = time.delta_secs();
+    let Some(speed) = speed else {
+        *last_speed = None;
+        power.set_if_neq(Power(0.0));
+        return;
+    };
+    if delta <= 0.0 {
+        return;
+    }
+
+    // Speeds are in km/h
+    let acceleration = last_speed.map_or(0.0, |last_speed| (speed.0 - last_speed) / 3.6 / delta);
+    *last_speed = Some(speed.0);
+    let target = model.power(speed.0 / 3.6, acceleration);
+    power.0.smooth_nudge(&target, POWER_SMOOTHING, delta);
+}
+
+#[derive(Component)]
+struct PowerGauge;
+
+#[derive(Component)]
+struct PowerReadout;
+
+/// Marker staying on the highest power, or the strongest regen, for a while before decaying
+#[derive(Component)]
+struct PeakMarker {
+    regen: bool,
+    position: f32,
+    held_for: f32,
+}
+
+fn setup(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<GaugeMaterial>>,
+    theme: Res<DashboardTheme>,
+) {
+    let peak_marker = |regen| {
+        (
+            Sprite::from_color(palettes::css::WHITE, vec2(4.0, 20.0)),
+            Transform::from_xyz(0.0, 0.0, 0.1),
+            PeakMarker {
+                regen,
+                position: 0.0,
+                held_for: 0.0,
+            },
+        )
+    };
+
+    commands.spawn((
+        Widget::PowerMeter,
+        children![
+            (
+                Mesh2d(meshes.add(Rectangle::from_length(METER_RADIUS * 2.0))),
+                // Regen on the left half, power on the right half
+                MeshMaterial2d(
+                    materials.add(GaugeMaterial {
+                        gradient: vec![
+                            (0.45, Color::from(palettes::tailwind::GREEN_500)),
+                            (0.55, Color::from(palettes::tailwind::SKY_500)),
+                            (1.0, Color::from(palettes::tailwind::ORANGE_500)),
+                        ],
+                        background: theme.palette.progress_background,
+                        ticks: 4,
+                        tick_color: theme.palette.text,
+                        ..GaugeMaterial::radial(-METER_ANGLE, METER_ANGLE, 0.75, Color::NONE)
+                            .with_range(-1.0, 1.0)
+                    })
+                ),
+                PowerGauge,
+            ),
+            (
+                Text2d::new(""),
+                TextFont::from_font_size(20.0),
+                Transform::from_xyz(0.0, -20.0, 0.0),
+                Themed::Text,
+                PowerReadout,
+            ),
+            peak_marker(false),
+            peak_marker(true),
+        ],
+    ));
+}
+
+fn display_power(
+    power: Res<Power>,
+    model: Res<VehicleModel>,
+    time: Res<Time>,
+    gauge: Single<&MeshMaterial2d<GaugeMaterial>, With<PowerGauge>>,
+    mut gauge_materials: ResMut<Assets<GaugeMaterial>>,
+    mut readout: Single<&mut Text2d, With<PowerReadout>>,
+    mut markers: Query<(&mut PeakMarker, &mut Transform)>,
+) {
+    let position = model.meter_position(power.0);
+    if power.is_changed() {
+        gauge_materials.get_mut(*gauge).unwrap().value = position;
+        readout.0 = format!("{:.0} kW", power.0);
+    }
+
+    let delta = time.delta_secs();
+    for (mut marker, mut transform) in &mut markers {
+        let (current, peak) = if marker.regen {
+            (-position.min(0.0), -marker.position)
+        } else {
+            (position.max(0.0), marker.position)
+        };
+        let peak = if current >= peak {
+            marker.held_for = 0.0;
+            current
+        } else {
+            marker.held_for += delta;
+            if marker.held_for > PEAK_HOLD_DURATION {
+                (peak - PEAK_DECAY * delta).max(current)
+            } else {
+                peak
+            }
+        };
+        marker.position = if marker.regen { -peak } else { peak };
+
+        let angle = marker.position * METER_ANGLE;
+        let radius = METER_RADIUS * 0.875;
+        transform.translation = vec3(angle.sin() * radius, angle.cos() * radius, 0.1);
+        transform.rotation = Quat::from_rotation_z(-angle);
+    }
+}
+
+fn apply_theme(
+    theme: Res<DashboardTheme>,
+    gauge: Single<&MeshMaterial2d<GaugeMaterial>, With<PowerGauge>>,
+    mut gauge_materials: ResMut<Assets<GaugeMaterial>>,
+) {
+    if theme.is_changed() {
+        let material = gauge_materials.get_mut(*gauge).unwrap();
+        material.background = theme.palette.progress_background;
+        material.tick_color = theme.palette.text;
+    }
+}
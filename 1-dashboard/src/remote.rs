@@ -0,0 +1,631 @@
+use std::{
+    io,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+};
+
+use async_channel::TrySendError;
+use async_net::{TcpListener, TcpStream};
+use bevy::{
+    prelude::*,
+    tasks::{
+        IoTaskPool,
+        futures_lite::{
+            AsyncBufReadExt, AsyncReadExt, AsyncWriteExt,
+            io::{AsyncBufRead, BufReader},
+        },
+    },
+};
+use crossbeam::channel::{self, Receiver, Sender};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    battery::{BatteryLevel, BatteryStatus},
+    input::{VehicleData, keyboard_backend},
+    music::MusicState,
+    odometer::Distance,
+    speed::{self, MAX_SPEED, Speed},
+    telltale::{Warning, WarningEvent},
+    turn::{ActiveTurnSignal, TurnSignal},
+};
+
+const DEFAULT_RATE: f32 = 10.0;
+const MAX_HEADER_LINES: usize = 64;
+const MAX_LINE_LENGTH: usize = 8192;
+const MAX_BODY_LENGTH: usize = 1024;
+// Frames queued for a client before newer ones are dropped, so a slow client doesn't hold the others back
+const CLIENT_BACKLOG: usize = 4;
+
+pub fn remote_plugin(app: &mut App) {
+    let Some(settings) = RemoteSettings::from_args() else {
+        return;
+    };
+    app.insert_resource(settings)
+        .add_systems(Startup, start_server)
+        .add_systems(
+            Update,
+            (
+                (apply_commands, stream_telemetry).chain(),
+                hold_commanded_speed
+                    .after(speed::update_speed)
+                    .run_if(keyboard_backend.and(resource_exists::<Speed>)),
+            )
+                .run_if(resource_exists::<RemoteServer>),
+        );
+}
+
+/// Server enabled with `--remote <port>`, streaming `--remote-rate <hz>` times per second
+#[derive(Resource, Clone, Copy, Debug)]
+struct RemoteSettings {
+    port: u16,
+    rate: f32,
+}
+
+impl RemoteSettings {
+    fn from_args() -> Option<Self> {
+        let args: Vec<String> = std::env::args().collect();
+        let argument = |name: &str| {
+            args.windows(2)
+                .find(|arg| arg[0] == name)
+                .map(|arg| arg[1].clone())
+        };
+        let port = argument("--remote")?;
+        let Ok(port) = port.parse() else {
+            warn!("Invalid remote port {port}");
+            return None;
+        };
+        let rate = argument("--remote-rate")
+            .and_then(|rate| rate.parse().ok())
+            .filter(|rate: &f32| *rate > 0.0)
+            .unwrap_or(DEFAULT_RATE);
+        Some(Self { port, rate })
+    }
+}
+
+/// State of the dashboard sent to the clients, missing values are for simulations not running
+#[derive(Serialize, PartialEq, Debug)]
+struct Telemetry {
+    /// Speed in km/h
+    speed: Option<f32>,
+    /// Battery state of charge in percent
+    battery: Option<f32>,
+    battery_status: BatteryStatus,
+    /// Distance on the odometer in km
+    distance: Option<f32>,
+    turn_signal: Option<TurnSignal>,
+    song: Option<String>,
+}
+
+/// Command sent by a client, like `{"SetSpeed": 50}`, `{"TurnSignal": "Left"}` or `"NextSong"`
+#[derive(Deserialize, PartialEq, Debug)]
+enum RemoteCommand {
+    /// Speed in km/h, from 0 to `MAX_SPEED`
+    SetSpeed(f32),
+    TurnSignal(TurnSignal),
+    NextSong,
+}
+
+/// Command posted as JSON, `None` when it's invalid
+fn parse_command(body: &[u8]) -> Option<RemoteCommand> {
+    match serde_json::from_slice(body) {
+        Ok(RemoteCommand::SetSpeed(speed)) if !(0.0..=MAX_SPEED).contains(&speed) => {
+            warn!("Remote speed {speed} out of range");
+            None
+        }
+        Ok(command) => Some(command),
+        Err(error) => {
+            warn!("Invalid remote command: {error}");
+            None
+        }
+    }
+}
+
+/// Channel to a client of the telemetry stream
+type Client = async_channel::Sender<String>;
+
+#[derive(Resource)]
+struct RemoteServer {
+    commands: Receiver<RemoteCommand>,
+    new_clients: Receiver<Client>,
+    clients: Vec<Client>,
+    timer: Timer,
+    /// Speed set by a client, held against coasting until the driver takes over
+    commanded_speed: Option<f32>,
+}
+
+impl RemoteServer {
+    fn new(commands: Receiver<RemoteCommand>, new_clients: Receiver<Client>, rate: f32) -> Self {
+        Self {
+            commands,
+            new_clients,
+            clients: Vec::new(),
+            timer: Timer::from_seconds(1.0 / rate, TimerMode::Repeating),
+            commanded_speed: None,
+        }
+    }
+}
+
+fn start_server(mut commands: Commands, settings: Res<RemoteSettings>) {
+    // Only reachable from this machine
+    let bind = |ip: IpAddr| {
+        std::net::TcpListener::bind((ip, settings.port)).and_then(TcpListener::try_from)
+    };
+    let mut listeners = match bind(Ipv4Addr::LOCALHOST.into()) {
+        Ok(listener) => vec![listener],
+        Err(error) => {
+            error!(
+                "Couldn't start the remote server on port {}: {error}",
+                settings.port
+            );
+            commands.trigger(WarningEvent::Raise(Warning::SystemFault));
+            return;
+        }
+    };
+    info!(
+        "Streaming telemetry on http://{}:{}/telemetry",
+        Ipv4Addr::LOCALHOST,
+        settings.port
+    );
+    // Also on the IPv6 loopback, where `[::1]` requests arrive, when the machine has one
+    match bind(Ipv6Addr::LOCALHOST.into()) {
+        Ok(listener) => listeners.push(listener),
+        Err(error) => debug!("Remote server not reachable over IPv6: {error}"),
+    }
+
+    let (command_sender, command_receiver) = channel::unbounded();
+    let (client_sender, client_receiver) = channel::unbounded();
+    for listener in listeners {
+        IoTaskPool::get()
+            .spawn(serve(
+                listener,
+                command_sender.clone(),
+                client_sender.clone(),
+            ))
+            .detach();
+    }
+    commands.insert_resource(RemoteServer::new(
+        command_receiver,
+        client_receiver,
+        settings.rate,
+    ));
+}
+
+async fn serve(listener: TcpListener, commands: Sender<RemoteCommand>, clients: Sender<Client>) {
+    loop {
+        let stream = match listener.accept().await {
+            Ok((stream, _)) => stream,
+            Err(error) => {
+                error!("Remote server stopped: {error}");
+                return;
+            }
+        };
+        let (commands, clients) = (commands.clone(), clients.clone());
+        IoTaskPool::get()
+            .spawn(async move {
+                if let Err(error) = handle_connection(stream, commands, clients).await {
+                    debug!("Remote connection closed: {error}");
+                }
+            })
+            .detach();
+    }
+}
+
+#[derive(PartialEq, Debug)]
+struct Request {
+    method: String,
+    path: String,
+    content_length: usize,
+    host: Option<String>,
+    origin: Option<String>,
+}
+
+impl Request {
+    /// Parse the request line and headers of an HTTP/1.1 request
+    fn parse(lines: &[String]) -> Option<Self> {
+        let (request_line, headers) = lines.split_first()?;
+        let mut parts = request_line.split_whitespace();
+        let mut request = Request {
+            method: parts.next()?.to_owned(),
+            path: parts.next()?.to_owned(),
+            content_length: 0,
+            host: None,
+            origin: None,
+        };
+        for header in headers {
+            let (name, value) = header.split_once(':')?;
+            let value = value.trim();
+            if name.eq_ignore_ascii_case("content-length") {
+                request.content_length = value.parse().ok()?;
+            } else if name.eq_ignore_ascii_case("host") {
+                request.host = Some(value.to_owned());
+            } else if name.eq_ignore_ascii_case("origin") {
+                request.origin = Some(value.to_owned());
+            }
+        }
+        Some(request)
+    }
+}
+
+// Browsers send an origin with their requests, only pages served from this machine are allowed
+fn is_local_origin(origin: &str) -> bool {
+    let Some(host) = origin
+        .strip_prefix("http://")
+        .or_else(|| origin.strip_prefix("https://"))
+    else {
+        return false;
+    };
+    ["localhost", "127.0.0.1", "[::1]"]
+        .into_iter()
+        .any(|local| host == local || host.starts_with(&format!("{local}:")))
+}
+
+// Pages of other sites can resolve their own name to this machine, they are told apart by
+// the host they asked for
+fn is_local_host(host: &str, port: u16) -> bool {
+    ["localhost", "127.0.0.1", "[::1]"]
+        .into_iter()
+        .any(|local| host == format!("{local}:{port}") || (port == 80 && host == local))
+}
+
+fn response(status: &str, origin: Option<&str>, headers: &str) -> String {
+    let mut response = format!("HTTP/1.1 {status}\r\n{headers}");
+    if let Some(origin) = origin {
+        response += &format!("Access-Control-Allow-Origin: {origin}\r\nVary: Origin\r\n");
+    }
+    response + "\r\n"
+}
+
+/// Lines of the request line and headers, up to the empty line before the body
+async fn read_head(reader: &mut (impl AsyncBufRead + Unpin)) -> io::Result<Vec<String>> {
+    let mut lines = Vec::new();
+    loop {
+        let mut line = String::new();
+        let length = reader
+            .take(MAX_LINE_LENGTH as u64)
+            .read_line(&mut line)
+            .await?;
+        if length == MAX_LINE_LENGTH && !line.ends_with('\n') {
+            return Err(io::Error::other("header line too long"));
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            return Ok(lines);
+        }
+        if lines.len() == MAX_HEADER_LINES {
+            return Err(io::Error::other("too many headers"));
+        }
+        lines.push(line.to_owned());
+    }
+}
+
+async fn handle_connection(
+    stream: TcpStream,
+    commands: Sender<RemoteCommand>,
+    clients: Sender<Client>,
+) -> io::Result<()> {
+    let port = stream.local_addr()?.port();
+    let mut reader = BufReader::new(stream.clone());
+    let mut writer = stream;
+    let lines = read_head(&mut reader).await?;
+
+    let closed = "Connection: close\r\nContent-Length: 0\r\n";
+    let Some(request) = Request::parse(&lines) else {
+        return writer
+            .write_all(response("400 Bad Request", None, closed).as_bytes())
+            .await;
+    };
+    let origin = request.origin.as_deref();
+    let local_host = request
+        .host
+        .as_deref()
+        .is_some_and(|host| is_local_host(host, port));
+    if !local_host || origin.is_some_and(|origin| !is_local_origin(origin)) {
+        return writer
+            .write_all(response("403 Forbidden", None, closed).as_bytes())
+            .await;
+    }
+
+    match (request.method.as_str(), request.path.as_str()) {
+        ("GET", "/telemetry") => {
+            let (sender, receiver) = async_channel::bounded(CLIENT_BACKLOG);
+            if clients.send(sender).is_err() {
+                return Ok(());
+            }
+            let headers = "Content-Type: text/event-stream\r\nCache-Control: no-cache\r\n";
+            writer
+                .write_all(response("200 OK", origin, headers).as_bytes())
+                .await?;
+            while let Ok(telemetry) = receiver.recv().await {
+                writer
+                    .write_all(format!("data: {telemetry}\n\n").as_bytes())
+                    .await?;
+            }
+            Ok(())
+        }
+        ("POST", "/command") => {
+            if request.content_length > MAX_BODY_LENGTH {
+                return writer
+                    .write_all(response("413 Content Too Large", origin, closed).as_bytes())
+                    .await;
+            }
+            let mut body = vec![0; request.content_length];
+            reader.read_exact(&mut body).await?;
+            let status = match parse_command(&body) {
+                Some(command) => {
+                    let _ = commands.send(command);
+                    "204 No Content"
+                }
+                None => "400 Bad Request",
+            };
+            writer
+                .write_all(response(status, origin, closed).as_bytes())
+                .await
+        }
+        // Preflight of the commands sent as JSON by browsers
+        ("OPTIONS", "/command") => {
+            let headers = "Access-Control-Allow-Methods: POST\r\nAccess-Control-Allow-Headers: Content-Type\r\n";
+            writer
+                .write_all(response("204 No Content", origin, headers).as_bytes())
+                .await
+        }
+        _ => {
+            writer
+                .write_all(response("404 Not Found", origin, closed).as_bytes())
+                .await
+        }
+    }
+}
+
+fn apply_commands(
+    mut server: ResMut<RemoteServer>,
+    mut commands: Commands,
+    mut music: Option<ResMut<MusicState>>,
+) {
+    let server = &mut *server;
+    for command in server.commands.try_iter() {
+        match command {
+            RemoteCommand::SetSpeed(speed) => {
+                server.commanded_speed = Some(speed);
+                commands.trigger(VehicleData::Speed(speed));
+            }
+            RemoteCommand::TurnSignal(signal) => commands.trigger(signal),
+            RemoteCommand::NextSong => {
+                if let Some(music) = &mut music {
+                    music.next_song();
+                }
+            }
+        }
+    }
+}
+
+// The keyboard simulation would coast away from the speed set by a client
+fn hold_commanded_speed(
+    mut server: ResMut<RemoteServer>,
+    mut speed: ResMut<Speed>,
+    input: Res<ButtonInput<KeyCode>>,
+    battery_status: Res<State<BatteryStatus>>,
+) {
+    // The pedals give the control back to the driver, and the car stops to charge
+    if input.any_pressed([KeyCode::Space, KeyCode::ArrowDown])
+        || *battery_status.get() == BatteryStatus::Charging
+    {
+        server.commanded_speed = None;
+    }
+    if let Some(commanded_speed) = server.commanded_speed {
+        speed.0 = commanded_speed;
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn stream_telemetry(
+    mut server: ResMut<RemoteServer>,
+    time: Res<Time>,
+    speed: Option<Res<Speed>>,
+    battery: Option<Res<BatteryLevel>>,
+    battery_status: Res<State<BatteryStatus>>,
+    distance: Option<Res<Distance>>,
+    turn_signal: Res<ActiveTurnSignal>,
+    music: Option<Res<MusicState>>,
+) {
+    let server = &mut *server;
+    server.clients.extend(server.new_clients.try_iter());
+    if !server.timer.tick(time.delta()).just_finished() || server.clients.is_empty() {
+        return;
+    }
+
+    let telemetry = Telemetry {
+        speed: speed.map(|speed| speed.0),
+        battery: battery.map(|battery| battery.0),
+        battery_status: *battery_status.get(),
+        distance: distance.map(|distance| distance.0),
+        turn_signal: turn_signal.0,
+        song: music.map(|music| music.song()),
+    };
+    let json = match serde_json::to_string(&telemetry) {
+        Ok(json) => json,
+        Err(error) => {
+            error!("Couldn't serialize telemetry: {error}");
+            return;
+        }
+    };
+    // Disconnected clients are forgotten, and slow ones miss frames
+    server
+        .clients
+        .retain(|client| !matches!(client.try_send(json.clone()), Err(TrySendError::Closed(_))));
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::tasks::block_on;
+
+    use super::*;
+    use crate::{
+        gear::Gear,
+        input::input_plugin,
+        speed::speed_simulation_plugin,
+        testing::{hold, record, recorded, run_for, test_app},
+    };
+
+    fn remote_app(rate: f32) -> (App, Sender<RemoteCommand>, Sender<Client>) {
+        let (command_sender, command_receiver) = channel::unbounded();
+        let (client_sender, client_receiver) = channel::unbounded();
+        let mut app = test_app();
+        app.add_plugins(input_plugin)
+            .init_state::<BatteryStatus>()
+            .init_resource::<ActiveTurnSignal>()
+            .insert_resource(Speed(0.0))
+            .insert_resource(Distance(12.5))
+            .insert_resource(RemoteServer::new(command_receiver, client_receiver, rate))
+            .add_systems(
+                Update,
+                (
+                    (apply_commands, stream_telemetry).chain(),
+                    hold_commanded_speed.after(speed::update_speed),
+                ),
+            );
+        record::<TurnSignal>(&mut app);
+        (app, command_sender, client_sender)
+    }
+
+    #[test]
+    fn streams_at_the_configured_rate() {
+        let (mut app, _, clients) = remote_app(4.0);
+        let (client, frames) = async_channel::unbounded();
+        clients.send(client).unwrap();
+        run_for(&mut app, 1.0);
+
+        assert_eq!(frames.len(), 4);
+        let frame: serde_json::Value = serde_json::from_str(&frames.try_recv().unwrap()).unwrap();
+        assert_eq!(
+            frame,
+            serde_json::json!({
+                "speed": 0.0,
+                "battery": null,
+                "battery_status": "Unplugged",
+                "distance": 12.5,
+                "turn_signal": null,
+                "song": null,
+            })
+        );
+    }
+
+    #[test]
+    fn disconnected_clients_are_dropped() {
+        let (mut app, _, clients) = remote_app(10.0);
+        let (client, frames) = async_channel::unbounded();
+        clients.send(client).unwrap();
+        run_for(&mut app, 0.2);
+        drop(frames);
+        run_for(&mut app, 0.2);
+        assert!(app.world().resource::<RemoteServer>().clients.is_empty());
+    }
+
+    #[test]
+    fn commands_drive_the_dashboard() {
+        let (mut app, commands, _) = remote_app(10.0);
+        commands.send(RemoteCommand::SetSpeed(50.0)).unwrap();
+        commands
+            .send(RemoteCommand::TurnSignal(TurnSignal::Left))
+            .unwrap();
+        app.update();
+
+        assert_eq!(app.world().resource::<Speed>().0, 50.0);
+        assert_eq!(recorded::<TurnSignal>(&app), [TurnSignal::Left]);
+    }
+
+    #[test]
+    fn commanded_speed_is_held_until_the_driver_brakes() {
+        let (mut app, commands, _) = remote_app(10.0);
+        app.init_state::<Gear>()
+            .add_plugins(speed_simulation_plugin);
+        commands.send(RemoteCommand::SetSpeed(50.0)).unwrap();
+        run_for(&mut app, 2.0);
+        assert_eq!(app.world().resource::<Speed>().0, 50.0);
+
+        hold(&mut app, KeyCode::ArrowDown);
+        run_for(&mut app, 0.1);
+        assert!(app.world().resource::<Speed>().0 < 45.0);
+        assert_eq!(app.world().resource::<RemoteServer>().commanded_speed, None);
+    }
+
+    #[test]
+    fn commands_from_json() {
+        let parse = |json: &str| parse_command(json.as_bytes());
+        assert_eq!(
+            parse(r#"{"SetSpeed": 50}"#),
+            Some(RemoteCommand::SetSpeed(50.0))
+        );
+        assert_eq!(
+            parse(r#"{"TurnSignal": "Right"}"#),
+            Some(RemoteCommand::TurnSignal(TurnSignal::Right))
+        );
+        assert_eq!(parse(r#""NextSong""#), Some(RemoteCommand::NextSong));
+        assert_eq!(parse(r#"{"TurnSignal": "Up"}"#), None);
+        assert_eq!(parse(r#"{"SetSpeed": -10}"#), None);
+        assert_eq!(parse(r#"{"SetSpeed": 1000}"#), None);
+    }
+
+    #[test]
+    fn request_headers() {
+        let lines = [
+            "POST /command HTTP/1.1",
+            "Host: localhost:8080",
+            "content-length: 16",
+            "Origin: http://localhost:3000",
+        ]
+        .map(String::from);
+        assert_eq!(
+            Request::parse(&lines),
+            Some(Request {
+                method: "POST".into(),
+                path: "/command".into(),
+                content_length: 16,
+                host: Some("localhost:8080".into()),
+                origin: Some("http://localhost:3000".into()),
+            })
+        );
+        assert_eq!(Request::parse(&["GET".into()]), None);
+    }
+
+    #[test]
+    fn request_head_is_bounded() {
+        let read = |head: String| block_on(read_head(&mut head.as_bytes()));
+        let lines = read("GET /telemetry HTTP/1.1\r\nHost: localhost:8080\r\n\r\nbody".into());
+        assert_eq!(
+            lines.unwrap(),
+            ["GET /telemetry HTTP/1.1", "Host: localhost:8080"]
+        );
+
+        let long_header = format!(
+            "GET / HTTP/1.1\r\nCookie: {}\r\n\r\n",
+            "a".repeat(MAX_LINE_LENGTH)
+        );
+        assert!(read(long_header).is_err());
+        let many_headers = format!(
+            "GET / HTTP/1.1\r\n{}\r\n",
+            "Accept: */*\r\n".repeat(MAX_HEADER_LINES)
+        );
+        assert!(read(many_headers).is_err());
+    }
+
+    #[test]
+    fn only_local_origins() {
+        assert!(is_local_origin("http://localhost:3000"));
+        assert!(is_local_origin("http://127.0.0.1"));
+        assert!(is_local_origin("http://[::1]:8000"));
+        assert!(!is_local_origin("http://localhost.example.com"));
+        assert!(!is_local_origin("https://example.com"));
+        assert!(!is_local_origin("null"));
+    }
+
+    #[test]
+    fn only_local_hosts_on_the_bound_port() {
+        assert!(is_local_host("localhost:8080", 8080));
+        assert!(is_local_host("127.0.0.1:8080", 8080));
+        assert!(is_local_host("[::1]:8080", 8080));
+        assert!(is_local_host("localhost", 80));
+        assert!(!is_local_host("localhost", 8080));
+        assert!(!is_local_host("localhost:3000", 8080));
+        assert!(!is_local_host("attacker.example.com:8080", 8080));
+        assert!(!is_local_host("localhost.example.com:8080", 8080));
+    }
+}
@@ -0,0 +1,283 @@
+use bevy::{
+    asset::{AssetLoader, LoadContext, io::Reader},
+    prelude::*,
+};
+use serde::Deserialize;
+
+use crate::{
+    odometer::Distance,
+    speed::{Speed, SpeedUnit},
+    telltale::Severity,
+    widgets::{Widget, widget_enabled, widget_turned_on},
+};
+
+pub fn road_plugin(app: &mut App) {
+    app.init_asset::<RoadProfile>()
+        .init_asset_loader::<RoadProfileLoader>()
+        .init_resource::<Overspeed>()
+        .add_systems(Startup, load_road)
+        .add_systems(
+            Update,
+            (
+                (update_speed_limit, update_overspeed).chain(),
+                setup.run_if(widget_turned_on(Widget::SpeedLimit)),
+                display_speed_limit.run_if(widget_enabled(Widget::SpeedLimit)),
+            )
+                .chain(),
+        );
+}
+
+/// Speed limits along a simulated road
+#[derive(Asset, TypePath, Deserialize)]
+struct RoadProfile {
+    /// Sorted by distance once loaded, each segment lasts until the next one
+    segments: Vec<RoadSegment>,
+    /// If set, the road starts again from the beginning after this distance in km
+    #[serde(default)]
+    loop_length: Option<f32>,
+    /// Speed over the limit in km/h before the dashboard turns amber
+    #[serde(default = "default_caution_margin")]
+    caution_margin: f32,
+    /// Speed over the limit in km/h before the dashboard turns red
+    #[serde(default = "default_critical_margin")]
+    critical_margin: f32,
+}
+
+#[derive(Deserialize)]
+struct RoadSegment {
+    /// Distance in km where the segment starts
+    from: f32,
+    /// Speed limit in km/h
+    limit: f32,
+}
+
+fn default_caution_margin() -> f32 {
+    0.0
+}
+
+fn default_critical_margin() -> f32 {
+    10.0
+}
+
+impl RoadProfile {
+    fn parse(bytes: &[u8]) -> Result<Self, BevyError> {
+        let mut profile: Self = ron::de::from_bytes(bytes)?;
+        if profile
+            .segments
+            .iter()
+            .any(|segment| !segment.from.is_finite())
+        {
+            return Err("Road segments must start at a finite distance".into());
+        }
+        profile.segments.sort_by(|a, b| a.from.total_cmp(&b.from));
+        Ok(profile)
+    }
+
+    fn limit_at(&self, distance: f32) -> Option<f32> {
+        let distance = match self.loop_length {
+            Some(length) if length > 0.0 => distance % length,
+            _ => distance,
+        };
+        self.segments
+            .iter()
+            .take_while(|segment| segment.from <= distance)
+            .last()
+            .map(|segment| segment.limit)
+    }
+}
+
+#[derive(Default, TypePath)]
+struct RoadProfileLoader;
+
+impl AssetLoader for RoadProfileLoader {
+    type Asset = RoadProfile;
+    type Settings = ();
+    type Error = BevyError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        RoadProfile::parse(&bytes)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["road.ron"]
+    }
+}
+
+#[derive(Resource)]
+struct Road(Handle<RoadProfile>);
+
+/// Speed limit in km/h at the current position on the road
+#[derive(Resource, Clone, Copy, PartialEq, Debug)]
+pub struct SpeedLimit(pub f32);
+
+/// How much the car is over the speed limit, if at all
+#[derive(Resource, Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Overspeed(pub Option<Severity>);
+
+fn load_road(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(Road(asset_server.load("commute.road.ron")));
+}
+
+fn update_speed_limit(
+    mut commands: Commands,
+    road: Res<Road>,
+    profiles: Res<Assets<RoadProfile>>,
+    distance: Option<Res<Distance>>,
+    limit: Option<ResMut<SpeedLimit>>,
+) {
+    let Some(profile) = profiles.get(&road.0) else {
+        return;
+    };
+    let distance = distance.map_or(0.0, |distance| distance.0);
+    match (profile.limit_at(distance), limit) {
+        (Some(new_limit), Some(mut limit)) => {
+            limit.set_if_neq(SpeedLimit(new_limit));
+        }
+        (Some(new_limit), None) => commands.insert_resource(SpeedLimit(new_limit)),
+        (None, Some(_)) => commands.remove_resource::<SpeedLimit>(),
+        (None, None) => (),
+    }
+}
+
+fn update_overspeed(
+    road: Res<Road>,
+    profiles: Res<Assets<RoadProfile>>,
+    speed: Option<Res<Speed>>,
+    limit: Option<Res<SpeedLimit>>,
+    mut overspeed: ResMut<Overspeed>,
+) {
+    let level = match (profiles.get(&road.0), speed, limit) {
+        (Some(profile), Some(speed), Some(limit)) => {
+            let over = speed.0 - limit.0;
+            if over > profile.critical_margin {
+                Some(Severity::Critical)
+            } else if over > profile.caution_margin {
+                Some(Severity::Caution)
+            } else {
+                None
+            }
+        }
+        _ => None,
+    };
+    overspeed.set_if_neq(Overspeed(level));
+}
+
+#[derive(Component)]
+struct SpeedLimitSign;
+
+fn setup(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    commands.spawn((
+        Widget::SpeedLimit,
+        children![
+            (
+                Mesh2d(meshes.add(Circle::new(50.0))),
+                MeshMaterial2d(materials.add(Color::srgb(0.8, 0.1, 0.1))),
+            ),
+            (
+                Mesh2d(meshes.add(Circle::new(40.0))),
+                MeshMaterial2d(materials.add(Color::WHITE)),
+                Transform::from_xyz(0.0, 0.0, 0.1),
+            ),
+            (
+                Text2d::new(""),
+                TextFont::from_font_size(36.0),
+                TextColor(Color::BLACK),
+                Transform::from_xyz(0.0, 0.0, 0.2),
+                SpeedLimitSign,
+            ),
+        ],
+    ));
+}
+
+fn display_speed_limit(
+    mut sign: Single<&mut Text2d, With<SpeedLimitSign>>,
+    limit: Option<Res<SpeedLimit>>,
+    unit: Option<Res<SpeedUnit>>,
+    added: Query<(), Added<SpeedLimitSign>>,
+    // The limit is removed past the end of the road, which isn't a change
+    mut shown_limit: Local<Option<f32>>,
+) {
+    let limit_value = limit.as_ref().map(|limit| limit.0);
+    let changed = limit.as_ref().is_some_and(|limit| limit.is_changed())
+        || *shown_limit != limit_value
+        || unit.as_ref().is_some_and(|unit| unit.is_changed())
+        || !added.is_empty();
+    if !changed {
+        return;
+    }
+    *shown_limit = limit_value;
+    let unit = unit.map_or(SpeedUnit::Kmh, |unit| *unit);
+    sign.0 = match limit {
+        Some(limit) => format!("{:.0}", unit.convert(limit.0)),
+        None => "--".to_string(),
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::test_app;
+
+    fn profile(ron: &str) -> RoadProfile {
+        RoadProfile::parse(ron.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn limit_of_the_segment_at_the_distance() {
+        let road = profile("(segments: [(from: 1.0, limit: 50.0), (from: 3.0, limit: 90.0)])");
+        assert_eq!(road.limit_at(0.5), None);
+        assert_eq!(road.limit_at(1.0), Some(50.0));
+        assert_eq!(road.limit_at(2.9), Some(50.0));
+        assert_eq!(road.limit_at(100.0), Some(90.0));
+    }
+
+    #[test]
+    fn looped_road_starts_again() {
+        let road = profile(
+            "(segments: [(from: 0.0, limit: 50.0), (from: 2.0, limit: 90.0)], loop_length: Some(5.0))",
+        );
+        assert_eq!(road.limit_at(4.9), Some(90.0));
+        assert_eq!(road.limit_at(5.5), Some(50.0));
+        assert_eq!(road.limit_at(12.0), Some(90.0));
+    }
+
+    #[test]
+    fn segments_are_sorted_when_loaded() {
+        let road = profile(
+            "(segments: [(from: 4.0, limit: 110.0), (from: 0.0, limit: 50.0), (from: 2.0, limit: 80.0)])",
+        );
+        assert_eq!(road.limit_at(1.0), Some(50.0));
+        assert_eq!(road.limit_at(3.0), Some(80.0));
+        assert_eq!(road.limit_at(5.0), Some(110.0));
+        assert!(RoadProfile::parse(b"(segments: [(from: inf, limit: 50.0)])").is_err());
+    }
+
+    #[test]
+    fn sign_is_cleared_past_the_end_of_the_road() {
+        let mut app = test_app();
+        app.add_systems(Update, display_speed_limit)
+            .insert_resource(SpeedLimit(80.0));
+        let sign = app
+            .world_mut()
+            .spawn((Text2d::default(), SpeedLimitSign))
+            .id();
+        let text = |app: &App| app.world().get::<Text2d>(sign).unwrap().0.clone();
+
+        app.update();
+        assert_eq!(text(&app), "80");
+        app.world_mut().remove_resource::<SpeedLimit>();
+        app.update();
+        assert_eq!(text(&app), "--");
+    }
+}
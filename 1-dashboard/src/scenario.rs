@@ -0,0 +1,315 @@
+use std::{fs, time::Duration};
+
+use bevy::{
+    app::{PluginGroupBuilder, ScheduleRunnerPlugin},
+    input::InputSystems,
+    prelude::*,
+    render::{RenderPlugin, settings::WgpuSettings},
+    time::TimeUpdateStrategy,
+    window::ExitCondition,
+    winit::WinitPlugin,
+};
+use serde::Deserialize;
+
+use crate::{
+    battery::{BatteryLevel, BatteryStatus},
+    body::Door,
+    gear::Gear,
+    odometer::Distance,
+    speed::Speed,
+    telltale::{ActiveWarnings, Warning},
+};
+
+// Scenarios run at a fixed frame rate so they give the same result on every machine
+const FRAME_DURATION: f32 = 1.0 / 60.0;
+// How long the scenario keeps running after the last input or check
+const SCENARIO_TAIL: f32 = 1.0;
+
+pub fn scenario_plugin(app: &mut App) {
+    let Some(path) = scenario_path() else {
+        return;
+    };
+    match fs::read_to_string(&path).map(|content| ron::from_str::<Scenario>(&content)) {
+        Ok(Ok(scenario)) => {
+            info!("Running scenario {path}");
+            app.insert_resource(ScenarioRun::new(scenario))
+                .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f32(
+                    FRAME_DURATION,
+                )))
+                .add_systems(PreUpdate, apply_inputs.after(InputSystems))
+                .add_systems(
+                    Last,
+                    (run_checks, finish_scenario)
+                        .chain()
+                        .in_set(ScenarioSystems),
+                );
+        }
+        Ok(Err(error)) => {
+            error!("Invalid scenario {path}: {error}");
+            app.add_systems(Startup, |mut exit: MessageWriter<AppExit>| {
+                exit.write(AppExit::error());
+            });
+        }
+        Err(error) => {
+            error!("Couldn't read scenario {path}: {error}");
+            app.add_systems(Startup, |mut exit: MessageWriter<AppExit>| {
+                exit.write(AppExit::error());
+            });
+        }
+    }
+}
+
+/// Checks of the running scenario, which exits the app at the end of the scenario
+#[derive(SystemSet, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct ScenarioSystems;
+
+/// Path of the scenario given with `--scenario <file>`
+pub fn scenario_path() -> Option<String> {
+    let args: Vec<String> = std::env::args().collect();
+    args.windows(2)
+        .find(|arg| arg[0] == "--scenario")
+        .map(|arg| arg[1].clone())
+}
+
+/// Default plugins without a window or a renderer, so scenarios can run without a display
+pub fn headless_plugins() -> PluginGroupBuilder {
+    DefaultPlugins
+        .build()
+        .set(WindowPlugin {
+            primary_window: None,
+            exit_condition: ExitCondition::DontExit,
+            ..default()
+        })
+        .set(RenderPlugin {
+            render_creation: WgpuSettings {
+                backends: None,
+                ..default()
+            }
+            .into(),
+            ..default()
+        })
+        .disable::<WinitPlugin>()
+        .add(ScheduleRunnerPlugin::run_loop(Duration::ZERO))
+}
+
+/// Timed inputs and the checks expected to pass, with times in seconds from the start
+#[derive(Deserialize)]
+struct Scenario {
+    #[serde(default)]
+    inputs: Vec<TimedInput>,
+    #[serde(default)]
+    checks: Vec<TimedCheck>,
+}
+
+#[derive(Deserialize)]
+struct TimedInput {
+    at: f32,
+    input: ScenarioInput,
+    /// How long the input is held, otherwise it is released on the next frame
+    #[serde(default)]
+    hold: f32,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug)]
+enum ScenarioInput {
+    Throttle,
+    Brake,
+    SignalLeft,
+    SignalRight,
+    SignalStop,
+    PlugCharger,
+    PlugFastCharger,
+    ShiftUp,
+    ShiftDown,
+    ToggleDoor(Door),
+    ToggleSeatbelt,
+    TogglePassengerSeatbelt,
+    Headlights,
+    FogLights,
+    Key(KeyCode),
+}
+
+impl ScenarioInput {
+    fn keys(self) -> Vec<KeyCode> {
+        match self {
+            ScenarioInput::Throttle => vec![KeyCode::Space],
+            ScenarioInput::Brake => vec![KeyCode::ArrowDown],
+            ScenarioInput::SignalLeft => vec![KeyCode::ArrowLeft],
+            ScenarioInput::SignalRight => vec![KeyCode::ArrowRight],
+            ScenarioInput::SignalStop => vec![KeyCode::Enter],
+            ScenarioInput::PlugCharger => vec![KeyCode::KeyP],
+            ScenarioInput::PlugFastCharger => vec![KeyCode::ShiftLeft, KeyCode::KeyP],
+            ScenarioInput::ShiftUp => vec![KeyCode::PageUp],
+            ScenarioInput::ShiftDown => vec![KeyCode::PageDown],
+            ScenarioInput::ToggleDoor(door) => vec![door.key()],
+            ScenarioInput::ToggleSeatbelt => vec![KeyCode::KeyB],
+            ScenarioInput::TogglePassengerSeatbelt => vec![KeyCode::ShiftLeft, KeyCode::KeyB],
+            ScenarioInput::Headlights => vec![KeyCode::KeyL],
+            ScenarioInput::FogLights => vec![KeyCode::KeyO],
+            ScenarioInput::Key(key) => vec![key],
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct TimedCheck {
+    at: f32,
+    expect: Expectation,
+}
+
+#[derive(Deserialize, Debug)]
+enum Expectation {
+    /// Speed in km/h
+    Speed(Comparison),
+    /// Battery level in percent
+    Battery(Comparison),
+    /// Distance on the odometer in km
+    Distance(Comparison),
+    BatteryStatus(BatteryStatus),
+    Gear(Gear),
+    /// The warning or telltale is shown
+    Warning(Warning),
+    NoWarning(Warning),
+}
+
+#[derive(Deserialize, Clone, Copy, Debug)]
+enum Comparison {
+    Below(f32),
+    Above(f32),
+    Between(f32, f32),
+}
+
+impl Comparison {
+    fn matches(self, value: f32) -> bool {
+        match self {
+            Comparison::Below(limit) => value < limit,
+            Comparison::Above(limit) => value > limit,
+            Comparison::Between(low, high) => (low..=high).contains(&value),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum InputState {
+    Pending,
+    Held,
+    Done,
+}
+
+#[derive(Resource)]
+struct ScenarioRun {
+    scenario: Scenario,
+    inputs: Vec<InputState>,
+    next_check: usize,
+    failures: usize,
+    end: f32,
+}
+
+impl ScenarioRun {
+    fn new(mut scenario: Scenario) -> Self {
+        scenario.checks.sort_by(|a, b| a.at.total_cmp(&b.at));
+        let end = scenario
+            .inputs
+            .iter()
+            .map(|input| input.at + input.hold)
+            .chain(scenario.checks.iter().map(|check| check.at))
+            .fold(0.0, f32::max)
+            + SCENARIO_TAIL;
+        Self {
+            inputs: vec![InputState::Pending; scenario.inputs.len()],
+            scenario,
+            next_check: 0,
+            failures: 0,
+            end,
+        }
+    }
+}
+
+fn apply_inputs(
+    mut run: ResMut<ScenarioRun>,
+    mut keyboard: ResMut<ButtonInput<KeyCode>>,
+    time: Res<Time>,
+) {
+    let now = time.elapsed_secs();
+    let run = &mut *run;
+
+    // Released before pressing new inputs, so a tap lasts at least one frame
+    for (input, state) in run.scenario.inputs.iter().zip(&mut run.inputs) {
+        if *state == InputState::Held && input.at + input.hold <= now {
+            for key in input.input.keys() {
+                keyboard.release(key);
+            }
+            *state = InputState::Done;
+        }
+    }
+    for (input, state) in run.scenario.inputs.iter().zip(&mut run.inputs) {
+        if *state == InputState::Pending && input.at <= now {
+            for key in input.input.keys() {
+                keyboard.press(key);
+            }
+            *state = InputState::Held;
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn run_checks(
+    mut run: ResMut<ScenarioRun>,
+    time: Res<Time>,
+    speed: Option<Res<Speed>>,
+    battery: Option<Res<BatteryLevel>>,
+    distance: Option<Res<Distance>>,
+    battery_status: Res<State<BatteryStatus>>,
+    gear: Res<State<Gear>>,
+    warnings: Res<ActiveWarnings>,
+) {
+    let now = time.elapsed_secs();
+    while let Some(check) = run.scenario.checks.get(run.next_check)
+        && check.at <= now
+    {
+        let compare = |comparison: Comparison, value: Option<f32>| match value {
+            Some(value) => (comparison.matches(value), format!("{value:.2}")),
+            None => (false, "missing".to_string()),
+        };
+        let (passed, actual) = match check.expect {
+            Expectation::Speed(comparison) => {
+                compare(comparison, speed.as_ref().map(|speed| speed.0))
+            }
+            Expectation::Battery(comparison) => {
+                compare(comparison, battery.as_ref().map(|battery| battery.0))
+            }
+            Expectation::Distance(comparison) => {
+                compare(comparison, distance.as_ref().map(|distance| distance.0))
+            }
+            Expectation::BatteryStatus(status) => (
+                *battery_status.get() == status,
+                format!("{:?}", battery_status.get()),
+            ),
+            Expectation::Gear(expected) => (*gear.get() == expected, format!("{:?}", gear.get())),
+            Expectation::Warning(warning) => (warnings.contains(warning), "not shown".to_string()),
+            Expectation::NoWarning(warning) => (!warnings.contains(warning), "shown".to_string()),
+        };
+
+        if passed {
+            info!("PASS at {:.1}s: {:?}", check.at, check.expect);
+        } else {
+            error!("FAIL at {:.1}s: {:?}, got {actual}", check.at, check.expect);
+            run.failures += 1;
+        }
+        run.next_check += 1;
+    }
+}
+
+fn finish_scenario(run: Res<ScenarioRun>, time: Res<Time>, mut exit: MessageWriter<AppExit>) {
+    if time.elapsed_secs() < run.end {
+        return;
+    }
+    let total = run.scenario.checks.len();
+    if run.failures == 0 {
+        info!("Scenario passed: {total} checks");
+        exit.write(AppExit::Success);
+    } else {
+        error!("Scenario failed: {} of {total} checks", run.failures);
+        exit.write(AppExit::error());
+    }
+}
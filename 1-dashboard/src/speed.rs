@@ -3,40 +3,99 @@ use bevy::{
     prelude::*,
 };
 
-#[cfg(feature = "battery")]
-use crate::battery::BatteryStatus;
+use crate::{
+    battery::BatteryStatus,
+    gauge::GaugeMaterial,
+    gear::Gear,
+    input::keyboard_backend,
+    road::Overspeed,
+    theme::{self, DashboardTheme, Themed},
+    widgets::{Widget, widget_enabled, widget_turned_on},
+};
+
+pub const MAX_SPEED: f32 = 160.0;
+pub const REDLINE_SPEED: f32 = 130.0;
+const MAX_REVERSE_SPEED: f32 = 20.0;
+/// Speed in km/h gained per second with the throttle held
+pub const THROTTLE_RATE: f32 = 60.0;
+/// Speed in km/h lost per second with the brake held
+pub const BRAKE_RATE: f32 = 120.0;
+/// Decay rate of the speed when coasting, a fraction of the speed lost per second
+pub const COASTING_DECAY: f32 = 0.75;
+
+// Spring constants for the hand, slightly under-damped so it overshoots a bit
+const HAND_STIFFNESS: f32 = 80.0;
+const HAND_DAMPING: f32 = 12.0;
 
 pub fn speed_plugin(app: &mut App) {
-    app.add_systems(Startup, setup)
+    app.add_plugins(speed_simulation_plugin)
+        .add_systems(
+            Update,
+            (
+                setup.run_if(widget_turned_on(Widget::Speedometer)),
+                (rotate, switch_unit, display_speed, display_scale)
+                    .run_if(widget_enabled(Widget::Speedometer)),
+            )
+                .chain(),
+        )
+        .add_systems(
+            Update,
+            tint_overspeed
+                .after(theme::apply_theme)
+                .run_if(widget_enabled(Widget::Speedometer)),
+        );
+}
+
+/// Speed driven from the keyboard, kept while the speedometer is off
+pub fn speed_simulation_plugin(app: &mut App) {
+    app.init_resource::<Speed>()
+        .init_resource::<SpeedUnit>()
         .add_systems(
             Update,
             (
-                rotate,
-                #[cfg(not(feature = "battery"))]
-                update_speed,
-                #[cfg(feature = "battery")]
                 update_speed.run_if(in_state(BatteryStatus::Unplugged)),
-                #[cfg(feature = "battery")]
                 stop_car.run_if(in_state(BatteryStatus::Charging)),
-            ),
-        )
-        .insert_resource(Speed(0.0));
+            )
+                .run_if(keyboard_backend.and(resource_exists::<Speed>)),
+        );
 }
 
-fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+fn setup(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<GaugeMaterial>>,
+) {
     commands.spawn((
-        Transform::from_xyz(-300.0, 0.0, 0.0),
-        Visibility::Visible,
+        Widget::Speedometer,
+        Speedometer,
         children![
-            Sprite {
-                image: asset_server.load("speedometer/dial.png"),
-                color: palettes::css::GREEN.into(),
-                ..default()
-            },
             (
-                Transform::from_xyz(0.0, -125.0, 0.0).with_scale(Vec3::splat(0.5)),
+                Sprite::from_image(asset_server.load("speedometer/dial.png")),
+                Themed::Dial,
+                SpeedometerDial,
+            ),
+            (
+                Mesh2d(meshes.add(Rectangle::from_length(450.0))),
+                // Gauge angles go clockwise, dial angles counterclockwise
+                MeshMaterial2d(materials.add(GaugeMaterial {
+                    value: 1.0,
+                    ..GaugeMaterial::radial(
+                        -speed_to_angle(REDLINE_SPEED),
+                        -speed_to_angle(MAX_SPEED),
+                        205.0 / 225.0,
+                        Color::from(palettes::css::RED.with_alpha(0.6)),
+                    )
+                })),
+                Transform::from_xyz(0.0, -125.0, 0.1),
+            ),
+            (
+                Transform::from_xyz(0.0, -125.0, 0.2).with_scale(Vec3::splat(0.5)),
                 Visibility::Visible,
-                SpeedometerHand,
+                SpeedometerHand {
+                    angle: speed_to_angle(0.0),
+                    velocity: 0.0,
+                },
                 children![(
                     Sprite {
                         image: asset_server.load("speedometer/hand.png"),
@@ -44,33 +103,305 @@ fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
                     },
                     Transform::from_xyz(0.0, 150.0, 0.0),
                 ),],
-            )
+            ),
+            (
+                Text2d::new(""),
+                TextFont::from_font_size(32.0),
+                Transform::from_xyz(0.0, -190.0, 0.0),
+                Themed::Text,
+                SpeedReadout,
+            ),
         ],
     ));
 }
 
-#[derive(Resource)]
+/// Speed in km/h, negative when reversing
+#[derive(Resource, Default)]
 pub struct Speed(pub f32);
 
+#[derive(Resource, Default, Clone, Copy, PartialEq, Eq)]
+pub enum SpeedUnit {
+    #[default]
+    Kmh,
+    Mph,
+}
+
+impl SpeedUnit {
+    /// Convert a value in km or km/h to this unit
+    pub fn convert(self, value: f32) -> f32 {
+        match self {
+            SpeedUnit::Kmh => value,
+            SpeedUnit::Mph => value * 0.621_371,
+        }
+    }
+
+    pub fn speed_label(self) -> &'static str {
+        match self {
+            SpeedUnit::Kmh => "km/h",
+            SpeedUnit::Mph => "mph",
+        }
+    }
+
+    pub fn distance_label(self) -> &'static str {
+        match self {
+            SpeedUnit::Kmh => "km",
+            SpeedUnit::Mph => "mi",
+        }
+    }
+
+    fn scale_step(self) -> usize {
+        match self {
+            SpeedUnit::Kmh => 20,
+            SpeedUnit::Mph => 10,
+        }
+    }
+}
+
 #[derive(Component)]
-struct SpeedometerHand;
+struct Speedometer;
 
-fn rotate(mut transform: Single<&mut Transform, With<SpeedometerHand>>, speed: Res<Speed>) {
-    if speed.is_changed() {
-        transform.rotation = Quat::from_rotation_z(-speed.0 / 160.0 * 3.0 + 1.5);
+#[derive(Component)]
+struct SpeedometerDial;
+
+#[derive(Component)]
+struct SpeedometerHand {
+    angle: f32,
+    velocity: f32,
+}
+
+#[derive(Component)]
+struct SpeedReadout;
+
+#[derive(Component)]
+struct ScaleLabel;
+
+fn speed_to_angle(speed: f32) -> f32 {
+    -speed / MAX_SPEED * 3.0 + 1.5
+}
+
+// Position on the dial for a given angle, relative to the center of the speedometer
+fn dial_position(angle: f32, radius: f32) -> Vec2 {
+    vec2(-angle.sin(), angle.cos()) * radius + vec2(0.0, -125.0)
+}
+
+fn rotate(
+    hand: Single<(&mut Transform, &mut SpeedometerHand)>,
+    speed: Res<Speed>,
+    time: Res<Time>,
+) {
+    let (mut transform, mut hand) = hand.into_inner();
+    // Avoid the spring exploding after a long frame
+    let delta = time.delta_secs().min(0.05);
+
+    let target = speed_to_angle(speed.0.abs());
+    let acceleration = HAND_STIFFNESS * (target - hand.angle) - HAND_DAMPING * hand.velocity;
+    hand.velocity += acceleration * delta;
+    hand.angle += hand.velocity * delta;
+
+    transform.rotation = Quat::from_rotation_z(hand.angle);
+}
+
+fn switch_unit(input: Res<ButtonInput<KeyCode>>, mut unit: ResMut<SpeedUnit>) {
+    if input.just_pressed(KeyCode::KeyU) {
+        *unit = match *unit {
+            SpeedUnit::Kmh => SpeedUnit::Mph,
+            SpeedUnit::Mph => SpeedUnit::Kmh,
+        };
+    }
+}
+
+fn display_speed(
+    mut text: Single<&mut Text2d, With<SpeedReadout>>,
+    speed: Res<Speed>,
+    unit: Res<SpeedUnit>,
+) {
+    if speed.is_changed() || unit.is_changed() {
+        text.0 = format!("{:.0} {}", unit.convert(speed.0.abs()), unit.speed_label());
     }
 }
 
-fn update_speed(mut speed: ResMut<Speed>, input: Res<ButtonInput<KeyCode>>, time: Res<Time>) {
+// Runs after the theme so that a theme change doesn't hide the overspeed tint
+fn tint_overspeed(
+    overspeed: Res<Overspeed>,
+    theme: Res<DashboardTheme>,
+    dial: Single<(&mut Sprite, Ref<SpeedometerDial>)>,
+    mut readout: Single<&mut TextColor, With<SpeedReadout>>,
+) {
+    let (mut dial, marker) = dial.into_inner();
+    if !overspeed.is_changed() && !theme.is_changed() && !marker.is_added() {
+        return;
+    }
+
+    match overspeed.0 {
+        Some(severity) => {
+            dial.color = severity.color();
+            readout.0 = severity.color();
+        }
+        None => {
+            dial.color = theme.palette.dial;
+            readout.0 = theme.palette.text;
+        }
+    }
+}
+
+fn display_scale(
+    mut commands: Commands,
+    unit: Res<SpeedUnit>,
+    speedometer: Single<Entity, With<Speedometer>>,
+    labels: Query<Entity, With<ScaleLabel>>,
+) {
+    if !unit.is_changed() {
+        return;
+    }
+
+    for label in &labels {
+        commands.entity(label).despawn();
+    }
+
+    let max = unit.convert(MAX_SPEED);
+    let step = unit.scale_step();
+    commands.entity(*speedometer).with_children(|parent| {
+        for value in (0..=max as usize).step_by(step) {
+            let angle = speed_to_angle(value as f32 / max * MAX_SPEED);
+            parent.spawn((
+                Text2d::new(value.to_string()),
+                TextFont::from_font_size(18.0),
+                Transform::from_translation(dial_position(angle, 180.0).extend(0.0)),
+                Themed::Text,
+                ScaleLabel,
+            ));
+        }
+    });
+}
+
+pub fn update_speed(
+    mut speed: ResMut<Speed>,
+    input: Res<ButtonInput<KeyCode>>,
+    gear: Res<State<Gear>>,
+    time: Res<Time>,
+) {
+    let delta = time.delta_secs();
     if input.pressed(KeyCode::Space) {
-        speed.0 = (speed.0 + 1.0).min(160.0);
+        let step = THROTTLE_RATE * delta;
+        match gear.get() {
+            Gear::Drive => speed.0 = (speed.0 + step).min(MAX_SPEED),
+            Gear::Reverse => speed.0 = (speed.0 - step).max(-MAX_REVERSE_SPEED),
+            Gear::Park | Gear::Neutral => (),
+        }
+    }
+    if input.pressed(KeyCode::ArrowDown) {
+        // Brake toward 0 whichever the direction
+        let step = BRAKE_RATE * delta;
+        speed.0 -= speed.0.clamp(-step, step);
     }
-    if speed.0 > 0.0 {
-        speed.0.smooth_nudge(&0.0, 0.75, time.delta_secs());
+    if speed.0 != 0.0 {
+        speed.0.smooth_nudge(&0.0, COASTING_DECAY, delta);
     }
 }
 
-#[cfg(feature = "battery")]
 fn stop_car(mut speed: ResMut<Speed>, time: Res<Time>) {
     speed.0.smooth_nudge(&0.0, 10.0, time.delta_secs());
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        input::InputBackend,
+        testing::{hold, release, run_for, set_state, test_app},
+    };
+
+    fn speed_app(speed: f32) -> App {
+        let mut app = test_app();
+        app.init_state::<BatteryStatus>()
+            .init_state::<Gear>()
+            .add_plugins(speed_simulation_plugin)
+            .insert_resource(Speed(speed));
+        app
+    }
+
+    fn speed(app: &App) -> f32 {
+        app.world().resource::<Speed>().0
+    }
+
+    #[test]
+    fn throttle_accelerates_in_drive() {
+        let mut app = speed_app(0.0);
+        hold(&mut app, KeyCode::Space);
+        run_for(&mut app, 1.0);
+        assert!(speed(&app) > 30.0);
+
+        run_for(&mut app, 30.0);
+        assert!(speed(&app) <= MAX_SPEED);
+    }
+
+    #[test]
+    fn throttle_reverses_in_reverse() {
+        let mut app = speed_app(0.0);
+        set_state(&mut app, Gear::Reverse);
+        hold(&mut app, KeyCode::Space);
+        run_for(&mut app, 10.0);
+        assert!(speed(&app) < 0.0);
+        assert!(speed(&app) >= -MAX_REVERSE_SPEED);
+    }
+
+    #[test]
+    fn throttle_does_nothing_in_park_or_neutral() {
+        for gear in [Gear::Park, Gear::Neutral] {
+            let mut app = speed_app(0.0);
+            set_state(&mut app, gear);
+            hold(&mut app, KeyCode::Space);
+            run_for(&mut app, 1.0);
+            assert_eq!(speed(&app), 0.0);
+        }
+    }
+
+    #[test]
+    fn coasting_slows_down() {
+        let mut app = speed_app(100.0);
+        run_for(&mut app, 1.0);
+        let coasting = speed(&app);
+        assert!(coasting < 100.0);
+        assert!(coasting > 0.0);
+
+        hold(&mut app, KeyCode::ArrowDown);
+        run_for(&mut app, 1.0);
+        assert_eq!(speed(&app), 0.0);
+
+        release(&mut app, KeyCode::ArrowDown);
+        run_for(&mut app, 1.0);
+        assert_eq!(speed(&app), 0.0);
+    }
+
+    #[test]
+    fn braking_works_backward() {
+        let mut app = speed_app(-15.0);
+        hold(&mut app, KeyCode::ArrowDown);
+        run_for(&mut app, 1.0);
+        assert_eq!(speed(&app), 0.0);
+    }
+
+    #[test]
+    fn charging_stops_the_car() {
+        let mut app = speed_app(80.0);
+        set_state(&mut app, BatteryStatus::Charging);
+        hold(&mut app, KeyCode::Space);
+        run_for(&mut app, 1.0);
+        assert!(speed(&app) < 1.0);
+    }
+
+    #[test]
+    fn other_backends_are_not_simulated() {
+        let mut app = speed_app(50.0);
+        app.insert_resource(InputBackend::Replay);
+        hold(&mut app, KeyCode::Space);
+        run_for(&mut app, 1.0);
+        assert_eq!(speed(&app), 50.0);
+    }
+
+    #[test]
+    fn speed_unit_conversion() {
+        assert_eq!(SpeedUnit::Kmh.convert(100.0), 100.0);
+        assert!((SpeedUnit::Mph.convert(100.0) - 62.137).abs() < 0.01);
+    }
+}
@@ -0,0 +1,388 @@
+use std::{
+    fs::{self, File},
+    io::{BufWriter, Write},
+};
+
+use bevy::prelude::*;
+
+use crate::{
+    battery::{BatteryLevel, BatteryStatus},
+    input::{InputBackend, VehicleData},
+    speed::Speed,
+    turn::TurnSignal,
+};
+
+// Changes smaller than this are not recorded, to keep the files small
+const RECORD_THRESHOLD: f32 = 0.1;
+const SEEK_STEP: f32 = 10.0;
+
+pub fn telemetry_plugin(app: &mut App) {
+    let args: Vec<String> = std::env::args().collect();
+    let argument = |name: &str| {
+        args.windows(2)
+            .find(|arg| arg[0] == name)
+            .map(|arg| arg[1].clone())
+    };
+
+    if let Some(path) = argument("--record") {
+        match File::create(&path) {
+            Ok(file) => {
+                app.insert_resource(Recorder::new(file))
+                    .add_systems(Update, (record, flush_recording))
+                    .add_observer(record_turn_signal);
+            }
+            Err(error) => error!("Couldn't create recording {path}: {error}"),
+        }
+    }
+
+    if let Some(path) = argument("--replay") {
+        match fs::read_to_string(&path).map(|content| parse_recording(&content)) {
+            Ok(Ok(events)) => {
+                app.insert_resource(Replay {
+                    events,
+                    cursor: 0,
+                    time: 0.0,
+                    rate: 1.0,
+                    paused: false,
+                })
+                .insert_resource(InputBackend::Replay)
+                .add_systems(Startup, setup_replay)
+                .add_systems(
+                    Update,
+                    (control_replay, play_replay, display_replay).chain(),
+                );
+            }
+            Ok(Err(line)) => error!("Invalid line {line} in recording {path}"),
+            Err(error) => error!("Couldn't read recording {path}: {error}"),
+        }
+    }
+}
+
+#[derive(Resource)]
+struct Recorder {
+    writer: BufWriter<File>,
+    last_speed: Option<f32>,
+    last_battery: Option<f32>,
+    last_charging: Option<bool>,
+}
+
+impl Recorder {
+    fn new(file: File) -> Self {
+        let mut writer = BufWriter::new(file);
+        let _ = writeln!(writer, "time,signal,value");
+        Self {
+            writer,
+            last_speed: None,
+            last_battery: None,
+            last_charging: None,
+        }
+    }
+
+    fn write(&mut self, time: f32, signal: &str, value: impl std::fmt::Display) {
+        if let Err(error) = writeln!(self.writer, "{time:.3},{signal},{value}") {
+            error!("Couldn't write recording: {error}");
+        }
+    }
+}
+
+fn record(
+    mut recorder: ResMut<Recorder>,
+    time: Res<Time>,
+    speed: Option<Res<Speed>>,
+    battery: Option<Res<BatteryLevel>>,
+    battery_status: Res<State<BatteryStatus>>,
+) {
+    let now = time.elapsed_secs();
+    let changed = |last: Option<f32>, value: f32| {
+        last.is_none_or(|last| (last - value).abs() >= RECORD_THRESHOLD)
+    };
+
+    if let Some(speed) = speed
+        && changed(recorder.last_speed, speed.0)
+    {
+        recorder.last_speed = Some(speed.0);
+        recorder.write(now, "speed", format!("{:.2}", speed.0));
+    }
+    if let Some(battery) = battery
+        && changed(recorder.last_battery, battery.0)
+    {
+        recorder.last_battery = Some(battery.0);
+        recorder.write(now, "battery", format!("{:.2}", battery.0));
+    }
+    let charging = *battery_status.get() == BatteryStatus::Charging;
+    if recorder.last_charging != Some(charging) {
+        recorder.last_charging = Some(charging);
+        recorder.write(now, "charging", charging as u8);
+    }
+}
+
+fn record_turn_signal(signal: On<TurnSignal>, mut recorder: ResMut<Recorder>, time: Res<Time>) {
+    let value = match *signal {
+        TurnSignal::Left => "left",
+        TurnSignal::Right => "right",
+        TurnSignal::Stop => "stop",
+    };
+    recorder.write(time.elapsed_secs(), "turn", value);
+}
+
+fn flush_recording(mut recorder: ResMut<Recorder>, mut exit: MessageReader<AppExit>) {
+    if exit.read().last().is_some() {
+        let _ = recorder.writer.flush();
+    }
+}
+
+/// Parse a recording, returning the first invalid line on error
+fn parse_recording(content: &str) -> Result<Vec<(f32, VehicleData)>, String> {
+    let mut events = Vec::new();
+    for line in content
+        .lines()
+        .skip(1)
+        .filter(|line| !line.trim().is_empty())
+    {
+        let parse = || -> Option<(f32, VehicleData)> {
+            let mut fields = line.split(',');
+            let time = fields.next()?.parse().ok()?;
+            let data = match (fields.next()?, fields.next()?) {
+                ("speed", value) => VehicleData::Speed(value.parse().ok()?),
+                ("battery", value) => VehicleData::StateOfCharge(value.parse().ok()?),
+                ("charging", value) => VehicleData::Charging(value == "1"),
+                ("turn", "left") => VehicleData::TurnSignal(TurnSignal::Left),
+                ("turn", "right") => VehicleData::TurnSignal(TurnSignal::Right),
+                ("turn", "stop") => VehicleData::TurnSignal(TurnSignal::Stop),
+                _ => return None,
+            };
+            Some((time, data))
+        };
+        events.push(parse().ok_or_else(|| line.to_string())?);
+    }
+    events.sort_by(|a, b| a.0.total_cmp(&b.0));
+    Ok(events)
+}
+
+#[derive(Resource)]
+struct Replay {
+    events: Vec<(f32, VehicleData)>,
+    /// Index of the next event to play
+    cursor: usize,
+    time: f32,
+    rate: f32,
+    paused: bool,
+}
+
+impl Replay {
+    fn duration(&self) -> f32 {
+        self.events.last().map_or(0.0, |(time, _)| *time)
+    }
+}
+
+#[derive(Component)]
+struct ReplayStatus;
+
+fn setup_replay(mut commands: Commands) {
+    commands.spawn((
+        Text::new(""),
+        TextFont::from_font_size(16.0),
+        Node {
+            position_type: PositionType::Absolute,
+            top: px(10),
+            left: px(10),
+            ..default()
+        },
+        ReplayStatus,
+    ));
+}
+
+fn control_replay(input: Res<ButtonInput<KeyCode>>, mut replay: ResMut<Replay>) {
+    if input.just_pressed(KeyCode::Space) {
+        replay.paused = !replay.paused;
+    }
+    if input.just_pressed(KeyCode::ArrowUp) {
+        replay.rate = (replay.rate * 2.0).min(16.0);
+    }
+    if input.just_pressed(KeyCode::ArrowDown) {
+        replay.rate = (replay.rate / 2.0).max(0.25);
+    }
+    if input.just_pressed(KeyCode::ArrowLeft) {
+        // Seeking backward replays from the start, so that the state is the same as when playing
+        replay.time = (replay.time - SEEK_STEP).max(0.0);
+        replay.cursor = 0;
+    }
+    if input.just_pressed(KeyCode::ArrowRight) {
+        replay.time = (replay.time + SEEK_STEP).min(replay.duration());
+    }
+}
+
+fn play_replay(mut commands: Commands, mut replay: ResMut<Replay>, time: Res<Time>) {
+    if !replay.paused {
+        replay.time = (replay.time + time.delta_secs() * replay.rate).min(replay.duration());
+    }
+
+    // Only the latest value of each signal matters when several events are due at once
+    let mut speed = None;
+    let mut battery = None;
+    let mut charging = None;
+    let mut turn_signal = None;
+    while let Some((event_time, data)) = replay.events.get(replay.cursor).copied() {
+        if event_time > replay.time {
+            break;
+        }
+        match data {
+            VehicleData::Speed(_) => speed = Some(data),
+            VehicleData::StateOfCharge(_) => battery = Some(data),
+            VehicleData::Charging(_) => charging = Some(data),
+            VehicleData::TurnSignal(_) => turn_signal = Some(data),
+        }
+        replay.cursor += 1;
+    }
+
+    for data in [speed, battery, charging, turn_signal]
+        .into_iter()
+        .flatten()
+    {
+        commands.trigger(data);
+    }
+}
+
+fn display_replay(replay: Res<Replay>, mut text: Single<&mut Text, With<ReplayStatus>>) {
+    if replay.is_changed() {
+        let format_time = |time: f32| {
+            let time = time as u32;
+            format!("{:0>2}:{:0>2}", time / 60, time % 60)
+        };
+        text.0 = format!(
+            "replay {} / {} x{}{}",
+            format_time(replay.time),
+            format_time(replay.duration()),
+            replay.rate,
+            if replay.paused { " (paused)" } else { "" }
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        input::input_plugin,
+        testing::{record as record_events, recorded, run_for, set_state, tap, test_app},
+    };
+
+    fn replay_app(events: Vec<(f32, VehicleData)>) -> App {
+        let mut app = test_app();
+        app.init_state::<BatteryStatus>()
+            .add_plugins(input_plugin)
+            .insert_resource(InputBackend::Replay)
+            .insert_resource(Speed(0.0))
+            .insert_resource(BatteryLevel(100.0))
+            .insert_resource(Replay {
+                events,
+                cursor: 0,
+                time: 0.0,
+                rate: 1.0,
+                paused: false,
+            })
+            .add_systems(Update, (control_replay, play_replay).chain());
+        record_events::<TurnSignal>(&mut app);
+        app
+    }
+
+    fn speed(app: &App) -> f32 {
+        app.world().resource::<Speed>().0
+    }
+
+    #[test]
+    fn recording_replays_the_same_drive() {
+        let path = std::env::temp_dir().join(format!("dashboard-{}.csv", std::process::id()));
+        let mut app = test_app();
+        app.init_state::<BatteryStatus>()
+            .insert_resource(Speed(0.0))
+            .insert_resource(BatteryLevel(80.0))
+            .insert_resource(Recorder::new(File::create(&path).unwrap()))
+            .add_systems(Update, record)
+            .add_observer(record_turn_signal);
+        app.update();
+        // Too small a change to be recorded
+        app.insert_resource(Speed(0.05));
+        app.update();
+        app.insert_resource(Speed(-12.5));
+        app.world_mut().trigger(TurnSignal::Left);
+        app.update();
+        app.insert_resource(BatteryLevel(79.5));
+        set_state(&mut app, BatteryStatus::Charging);
+        app.update();
+        app.world_mut()
+            .resource_mut::<Recorder>()
+            .writer
+            .flush()
+            .unwrap();
+        let content = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let events = parse_recording(&content).unwrap();
+        let speeds: Vec<f32> = events
+            .iter()
+            .filter_map(|(_, data)| match data {
+                VehicleData::Speed(speed) => Some(*speed),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(speeds, [0.0, -12.5]);
+
+        let mut app = replay_app(events);
+        run_for(&mut app, 1.0);
+        assert_eq!(speed(&app), -12.5);
+        assert_eq!(app.world().resource::<BatteryLevel>().0, 79.5);
+        assert_eq!(
+            *app.world().resource::<State<BatteryStatus>>().get(),
+            BatteryStatus::Charging
+        );
+        assert_eq!(recorded::<TurnSignal>(&app), [TurnSignal::Left]);
+    }
+
+    #[test]
+    fn invalid_and_unsorted_recordings() {
+        let content = "time,signal,value\n2.0,speed,20\n\n1.0,turn,left\n";
+        let events = parse_recording(content).unwrap();
+        assert_eq!(events[0].0, 1.0);
+        assert!(matches!(events[1].1, VehicleData::Speed(20.0)));
+
+        assert_eq!(
+            parse_recording("time,signal,value\n1.0,speed,fast\n").unwrap_err(),
+            "1.0,speed,fast"
+        );
+        assert!(parse_recording("time,signal,value\n1.0,gear,D\n").is_err());
+        assert!(parse_recording("time,signal,value\n1.0,speed\n").is_err());
+    }
+
+    #[test]
+    fn seeking_pausing_and_rate() {
+        let mut app = replay_app(vec![
+            (1.0, VehicleData::Speed(10.0)),
+            (5.0, VehicleData::Speed(20.0)),
+            (15.0, VehicleData::Speed(30.0)),
+            (30.0, VehicleData::Speed(40.0)),
+        ]);
+        run_for(&mut app, 6.0);
+        assert_eq!(speed(&app), 20.0);
+
+        tap(&mut app, KeyCode::ArrowRight);
+        assert_eq!(speed(&app), 30.0);
+
+        // Played again from the start up to the new time
+        tap(&mut app, KeyCode::ArrowLeft);
+        assert_eq!(speed(&app), 20.0);
+        assert_eq!(app.world().resource::<Replay>().cursor, 2);
+
+        tap(&mut app, KeyCode::Space);
+        let paused_at = app.world().resource::<Replay>().time;
+        run_for(&mut app, 2.0);
+        assert_eq!(app.world().resource::<Replay>().time, paused_at);
+
+        tap(&mut app, KeyCode::Space);
+        tap(&mut app, KeyCode::ArrowUp);
+        run_for(&mut app, 1.0);
+        let replay = app.world().resource::<Replay>();
+        assert_eq!(replay.rate, 2.0);
+        assert!((replay.time - paused_at - 2.0).abs() < 0.1);
+    }
+}
@@ -0,0 +1,276 @@
+use bevy::{platform::collections::HashMap, prelude::*};
+use serde::Deserialize;
+
+use crate::{
+    battery::BatteryLevel,
+    road::Overspeed,
+    speed::{REDLINE_SPEED, Speed},
+    widgets::{Widget, widget_enabled, widget_turned_on},
+};
+
+const LOW_BATTERY_LEVEL: f32 = 20.0;
+const TELLTALE_SPACING: f32 = 70.0;
+
+pub fn telltale_plugin(app: &mut App) {
+    app.init_resource::<WarningRegistry>()
+        .init_resource::<ActiveWarnings>()
+        .add_systems(
+            Update,
+            (
+                detect_warnings,
+                setup.run_if(widget_turned_on(Widget::Telltales)),
+                (display_telltales, blink_critical).run_if(widget_enabled(Widget::Telltales)),
+            )
+                .chain(),
+        )
+        .add_observer(update_warnings);
+}
+
+#[derive(Clone, Copy, Hash, PartialEq, Eq, Debug, Deserialize)]
+pub enum Warning {
+    LowBattery,
+    Overspeed,
+    DoorOpen,
+    Seatbelt,
+    SystemFault,
+    TirePressure,
+    LowBeam,
+    HighBeam,
+    FogLights,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Severity {
+    /// Not a fault, like the lights being on
+    Indicator,
+    Caution,
+    Critical,
+}
+
+impl Severity {
+    pub fn color(self) -> Color {
+        match self {
+            Severity::Indicator => Color::srgb(0.1, 0.8, 0.3),
+            Severity::Caution => Color::srgb(1.0, 0.7, 0.0),
+            Severity::Critical => Color::srgb(0.9, 0.1, 0.1),
+        }
+    }
+}
+
+pub struct WarningDefinition {
+    pub severity: Severity,
+    pub icon: &'static str,
+    pub chime: Option<&'static str>,
+}
+
+/// How each warning is displayed
+#[derive(Resource)]
+pub struct WarningRegistry(HashMap<Warning, WarningDefinition>);
+
+impl Default for WarningRegistry {
+    fn default() -> Self {
+        let mut registry = Self(HashMap::default());
+        registry.register(
+            Warning::LowBattery,
+            Severity::Caution,
+            "warnings/low_battery.png",
+            None,
+        );
+        registry.register(
+            Warning::Overspeed,
+            Severity::Caution,
+            "warnings/overspeed.png",
+            Some("warnings/chime.wav"),
+        );
+        registry.register(
+            Warning::DoorOpen,
+            Severity::Caution,
+            "warnings/door_open.png",
+            Some("warnings/chime.wav"),
+        );
+        registry.register(
+            Warning::Seatbelt,
+            Severity::Critical,
+            "warnings/seatbelt.png",
+            // The body controller sounds its own reminder while driving
+            None,
+        );
+        registry.register(
+            Warning::SystemFault,
+            Severity::Critical,
+            "warnings/system_fault.png",
+            Some("warnings/chime.wav"),
+        );
+        registry.register(
+            Warning::TirePressure,
+            Severity::Caution,
+            "warnings/tire_pressure.png",
+            Some("warnings/chime.wav"),
+        );
+        registry.register(
+            Warning::LowBeam,
+            Severity::Indicator,
+            "warnings/low_beam.png",
+            None,
+        );
+        registry.register(
+            Warning::HighBeam,
+            Severity::Indicator,
+            "warnings/high_beam.png",
+            None,
+        );
+        registry.register(
+            Warning::FogLights,
+            Severity::Indicator,
+            "warnings/fog_lights.png",
+            None,
+        );
+        registry
+    }
+}
+
+impl WarningRegistry {
+    pub fn register(
+        &mut self,
+        warning: Warning,
+        severity: Severity,
+        icon: &'static str,
+        chime: Option<&'static str>,
+    ) {
+        self.0.insert(
+            warning,
+            WarningDefinition {
+                severity,
+                icon,
+                chime,
+            },
+        );
+    }
+
+    pub fn get(&self, warning: Warning) -> &WarningDefinition {
+        &self.0[&warning]
+    }
+}
+
+#[derive(Event, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum WarningEvent {
+    Raise(Warning),
+    Clear(Warning),
+}
+
+/// Currently raised warnings, in the order they were raised
+#[derive(Resource, Default)]
+pub struct ActiveWarnings(Vec<Warning>);
+
+impl ActiveWarnings {
+    pub fn contains(&self, warning: Warning) -> bool {
+        self.0.contains(&warning)
+    }
+}
+
+fn update_warnings(
+    event: On<WarningEvent>,
+    mut commands: Commands,
+    mut active: ResMut<ActiveWarnings>,
+    registry: Res<WarningRegistry>,
+    asset_server: Res<AssetServer>,
+) {
+    match *event {
+        WarningEvent::Raise(warning) if !active.contains(warning) => {
+            active.0.push(warning);
+            if let Some(chime) = registry.get(warning).chime {
+                commands.spawn((
+                    AudioPlayer::new(asset_server.load(chime)),
+                    PlaybackSettings::DESPAWN,
+                ));
+            }
+        }
+        WarningEvent::Clear(warning) if active.contains(warning) => {
+            active.0.retain(|active| *active != warning);
+        }
+        _ => (),
+    }
+}
+
+fn detect_warnings(
+    mut commands: Commands,
+    active: Res<ActiveWarnings>,
+    battery: Option<Res<BatteryLevel>>,
+    speed: Option<Res<Speed>>,
+    overspeed: Res<Overspeed>,
+) {
+    let mut set = |warning, raised: bool| {
+        if raised != active.contains(warning) {
+            commands.trigger(if raised {
+                WarningEvent::Raise(warning)
+            } else {
+                WarningEvent::Clear(warning)
+            });
+        }
+    };
+    set(
+        Warning::LowBattery,
+        battery.is_some_and(|battery| battery.0 < LOW_BATTERY_LEVEL),
+    );
+    set(
+        Warning::Overspeed,
+        overspeed.0 == Some(Severity::Critical)
+            || speed.is_some_and(|speed| speed.0 > REDLINE_SPEED),
+    );
+}
+
+#[derive(Component)]
+struct TelltaleStrip;
+
+#[derive(Component)]
+struct CriticalBlink(Timer);
+
+fn setup(mut commands: Commands) {
+    commands.spawn((Widget::Telltales, TelltaleStrip));
+}
+
+fn display_telltales(
+    mut commands: Commands,
+    strip: Single<Entity, With<TelltaleStrip>>,
+    active: Res<ActiveWarnings>,
+    registry: Res<WarningRegistry>,
+    asset_server: Res<AssetServer>,
+    added: Query<(), Added<TelltaleStrip>>,
+) {
+    if !active.is_changed() && added.is_empty() {
+        return;
+    }
+
+    // Most severe first, then in the order they were raised
+    let mut warnings = active.0.clone();
+    warnings.sort_by_key(|warning| std::cmp::Reverse(registry.get(*warning).severity));
+
+    commands.entity(*strip).despawn_children();
+    let offset = (warnings.len() as f32 - 1.0) * TELLTALE_SPACING / 2.0;
+    for (i, warning) in warnings.into_iter().enumerate() {
+        let definition = registry.get(warning);
+        let mut telltale = commands.spawn((
+            Sprite {
+                image: asset_server.load(definition.icon),
+                color: definition.severity.color(),
+                ..default()
+            },
+            Transform::from_xyz(i as f32 * TELLTALE_SPACING - offset, 0.0, 0.0),
+            ChildOf(*strip),
+        ));
+        if definition.severity == Severity::Critical {
+            telltale.insert(CriticalBlink(Timer::from_seconds(
+                0.4,
+                TimerMode::Repeating,
+            )));
+        }
+    }
+}
+
+fn blink_critical(mut telltales: Query<(&mut CriticalBlink, &mut Visibility)>, time: Res<Time>) {
+    for (mut blink, mut visibility) in &mut telltales {
+        if blink.0.tick(time.delta()).just_finished() {
+            visibility.toggle_inherited_hidden();
+        }
+    }
+}
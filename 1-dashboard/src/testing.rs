@@ -0,0 +1,94 @@
+use std::time::Duration;
+
+use bevy::{
+    prelude::*,
+    state::{app::StatesPlugin, state::FreelyMutableState},
+    time::TimeUpdateStrategy,
+};
+
+use crate::input::InputBackend;
+
+pub const FRAME_DURATION: f32 = 1.0 / 60.0;
+
+/// App without rendering, advancing by a fixed duration on each update
+pub fn test_app() -> App {
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, StatesPlugin))
+        .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f32(
+            FRAME_DURATION,
+        )))
+        .init_resource::<ButtonInput<KeyCode>>()
+        .init_resource::<InputBackend>();
+    // The first update only starts the clock
+    app.update();
+    app
+}
+
+/// Run updates for the given number of simulated seconds
+pub fn run_for(app: &mut App, seconds: f32) {
+    for _ in 0..(seconds / FRAME_DURATION).round() as u32 {
+        app.update();
+    }
+}
+
+/// Press a key for a single update, like a tap on the keyboard
+pub fn tap(app: &mut App, key: KeyCode) {
+    app.world_mut()
+        .resource_mut::<ButtonInput<KeyCode>>()
+        .press(key);
+    app.update();
+    let mut input = app.world_mut().resource_mut::<ButtonInput<KeyCode>>();
+    input.release(key);
+    input.clear();
+}
+
+/// Keep a key pressed until it is released
+pub fn hold(app: &mut App, key: KeyCode) {
+    app.world_mut()
+        .resource_mut::<ButtonInput<KeyCode>>()
+        .press(key);
+}
+
+pub fn release(app: &mut App, key: KeyCode) {
+    app.world_mut()
+        .resource_mut::<ButtonInput<KeyCode>>()
+        .release(key);
+}
+
+/// Switch to a state, applied by the next update
+pub fn set_state<S: FreelyMutableState>(app: &mut App, state: S) {
+    app.world_mut().resource_mut::<NextState<S>>().set(state);
+    app.update();
+}
+
+/// Values recorded from the events triggered in the app, in order
+#[derive(Resource)]
+pub struct Recorded<T: Send + Sync + 'static>(pub Vec<T>);
+
+impl<T: Send + Sync + 'static> Default for Recorded<T> {
+    fn default() -> Self {
+        Self(Vec::new())
+    }
+}
+
+/// Record every event of this type triggered in the app
+pub fn record<E: Event + Clone>(app: &mut App) {
+    record_as(app, E::clone);
+}
+
+/// Record a value taken from every event of this type triggered in the app.
+/// Events of several types mapped to the same value type are recorded together, in order.
+pub fn record_as<E: Event, T: Send + Sync + 'static>(
+    app: &mut App,
+    value: impl Fn(&E) -> T + Send + Sync + 'static,
+) {
+    app.init_resource::<Recorded<T>>().add_observer(
+        move |event: On<E>, mut recorded: ResMut<Recorded<T>>| {
+            recorded.0.push(value(event.event()));
+        },
+    );
+}
+
+pub fn recorded<T: Send + Sync + 'static>(app: &App) -> &[T] {
+    &app.world().resource::<Recorded<T>>().0
+}
@@ -0,0 +1,187 @@
+use bevy::{color::palettes, prelude::*};
+
+const FADE_DURATION: f32 = 2.0;
+// Simulated hours per real second
+const CLOCK_SPEED: f32 = 0.1;
+const SUNRISE: f32 = 7.0;
+const SUNSET: f32 = 19.0;
+
+pub fn theme_plugin(app: &mut App) {
+    app.insert_resource(DashboardTheme {
+        mode: ThemeMode::Day,
+        blend: 0.0,
+        palette: Palette::DAY,
+    })
+    .insert_resource(ThemeClock { hour: 12.0 })
+    .add_systems(
+        Update,
+        ((toggle_theme, follow_clock), fade_theme, apply_theme).chain(),
+    );
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ThemeMode {
+    Day,
+    Night,
+}
+
+#[derive(Clone, Copy)]
+pub struct Palette {
+    pub background: Color,
+    pub dial: Color,
+    pub text: Color,
+    pub secondary_text: Color,
+    pub signal_off: Color,
+    pub signal_on: Color,
+    pub battery_empty: Color,
+    pub battery_full: Color,
+    pub battery_marker: Color,
+    pub progress: Color,
+    pub progress_background: Color,
+}
+
+impl Palette {
+    const DAY: Palette = Palette {
+        background: Color::srgb(0.17, 0.17, 0.18),
+        dial: Color::Srgba(palettes::css::GREEN),
+        text: Color::WHITE,
+        secondary_text: Color::Srgba(palettes::tailwind::GRAY_500),
+        signal_off: Color::Srgba(palettes::tailwind::GRAY_800),
+        signal_on: Color::WHITE,
+        battery_empty: Color::srgb(0.9, 0.1, 0.1),
+        battery_full: Color::srgb(0.1, 0.4, 0.9),
+        battery_marker: Color::srgb(0.0, 0.7, 0.8),
+        progress: Color::srgb(0.2, 0.3, 0.8),
+        progress_background: Color::srgb(0.2, 0.2, 0.2),
+    };
+
+    const NIGHT: Palette = Palette {
+        background: Color::srgb(0.02, 0.02, 0.03),
+        dial: Color::Srgba(palettes::tailwind::GREEN_800),
+        text: Color::Srgba(palettes::tailwind::GRAY_400),
+        secondary_text: Color::Srgba(palettes::tailwind::GRAY_600),
+        signal_off: Color::Srgba(palettes::tailwind::GRAY_900),
+        signal_on: Color::Srgba(palettes::tailwind::GRAY_300),
+        battery_empty: Color::Srgba(palettes::tailwind::RED_800),
+        battery_full: Color::Srgba(palettes::tailwind::BLUE_800),
+        battery_marker: Color::Srgba(palettes::tailwind::CYAN_800),
+        progress: Color::Srgba(palettes::tailwind::INDIGO_800),
+        progress_background: Color::Srgba(palettes::tailwind::GRAY_900),
+    };
+
+    fn mix(&self, other: &Palette, factor: f32) -> Palette {
+        let mix = |from: Color, to: Color| -> Color {
+            LinearRgba::from(from).mix(&to.into(), factor).into()
+        };
+        Palette {
+            background: mix(self.background, other.background),
+            dial: mix(self.dial, other.dial),
+            text: mix(self.text, other.text),
+            secondary_text: mix(self.secondary_text, other.secondary_text),
+            signal_off: mix(self.signal_off, other.signal_off),
+            signal_on: mix(self.signal_on, other.signal_on),
+            battery_empty: mix(self.battery_empty, other.battery_empty),
+            battery_full: mix(self.battery_full, other.battery_full),
+            battery_marker: mix(self.battery_marker, other.battery_marker),
+            progress: mix(self.progress, other.progress),
+            progress_background: mix(self.progress_background, other.progress_background),
+        }
+    }
+}
+
+/// Current colors of the dashboard, fading between the day and night palettes
+#[derive(Resource)]
+pub struct DashboardTheme {
+    pub mode: ThemeMode,
+    /// 0.0 is full day, 1.0 is full night
+    blend: f32,
+    pub palette: Palette,
+}
+
+/// Sprite or text colored from the current palette
+#[derive(Component, Clone, Copy)]
+pub enum Themed {
+    Dial,
+    Text,
+    SecondaryText,
+}
+
+impl Themed {
+    fn color(self, palette: &Palette) -> Color {
+        match self {
+            Themed::Dial => palette.dial,
+            Themed::Text => palette.text,
+            Themed::SecondaryText => palette.secondary_text,
+        }
+    }
+}
+
+#[derive(Resource)]
+struct ThemeClock {
+    hour: f32,
+}
+
+fn toggle_theme(input: Res<ButtonInput<KeyCode>>, mut theme: ResMut<DashboardTheme>) {
+    if input.just_pressed(KeyCode::KeyN) {
+        theme.mode = match theme.mode {
+            ThemeMode::Day => ThemeMode::Night,
+            ThemeMode::Night => ThemeMode::Day,
+        };
+    }
+}
+
+// Switch to day or night when the simulated clock goes past sunrise or sunset
+fn follow_clock(mut clock: ResMut<ThemeClock>, mut theme: ResMut<DashboardTheme>, time: Res<Time>) {
+    let is_day = |hour: f32| (SUNRISE..SUNSET).contains(&hour);
+
+    let was_day = is_day(clock.hour);
+    clock.hour = (clock.hour + time.delta_secs() * CLOCK_SPEED) % 24.0;
+    if was_day != is_day(clock.hour) {
+        theme.mode = if is_day(clock.hour) {
+            ThemeMode::Day
+        } else {
+            ThemeMode::Night
+        };
+    }
+}
+
+fn fade_theme(mut theme: ResMut<DashboardTheme>, time: Res<Time>) {
+    let target = match theme.mode {
+        ThemeMode::Day => 0.0,
+        ThemeMode::Night => 1.0,
+    };
+    if theme.blend == target {
+        return;
+    }
+
+    let step = time.delta_secs() / FADE_DURATION;
+    theme.blend = if theme.blend < target {
+        (theme.blend + step).min(target)
+    } else {
+        (theme.blend - step).max(target)
+    };
+    theme.palette = Palette::DAY.mix(&Palette::NIGHT, theme.blend);
+}
+
+pub fn apply_theme(
+    theme: Res<DashboardTheme>,
+    mut clear_color: ResMut<ClearColor>,
+    mut themed: Query<(Ref<Themed>, Option<&mut Sprite>, Option<&mut TextColor>)>,
+) {
+    if theme.is_changed() {
+        clear_color.0 = theme.palette.background;
+    }
+
+    for (themed, sprite, text_color) in &mut themed {
+        if !theme.is_changed() && !themed.is_added() {
+            continue;
+        }
+        let color = themed.color(&theme.palette);
+        if let Some(mut sprite) = sprite {
+            sprite.color = color;
+        }
+        if let Some(mut text_color) = text_color {
+            text_color.0 = color;
+        }
+    }
+}
@@ -0,0 +1,302 @@
+use bevy::prelude::*;
+
+use crate::{
+    battery::SIMULATED_HOURS_PER_SECOND,
+    speed::{Speed, SpeedUnit},
+    telltale::{Severity, Warning, WarningEvent},
+    theme::{DashboardTheme, Themed},
+    widgets::{CAR_SIZE, Widget, car_silhouette, widget_enabled, widget_turned_on},
+};
+
+/// Pressure in bar of the tires when cold
+const NOMINAL_PRESSURE: f32 = 2.5;
+const LOW_PRESSURE: f32 = 2.0;
+const AMBIENT_TEMPERATURE: f32 = 20.0;
+const ATMOSPHERIC_PRESSURE: f32 = 1.013;
+// Tires warm up with the speed, by this much in °C for each km/h
+const HEATING_PER_KMH: f32 = 0.25;
+// Fraction of the difference with the target temperature reached per second
+const HEATING_RATE: f32 = 0.05;
+const PSI_PER_BAR: f32 = 14.5038;
+const TIRE_SIZE: Vec2 = vec2(18.0, 36.0);
+
+pub fn tpms_plugin(app: &mut App) {
+    app.add_plugins(tpms_simulation_plugin).add_systems(
+        Update,
+        (
+            setup.run_if(widget_turned_on(Widget::TirePressure)),
+            display.run_if(widget_enabled(Widget::TirePressure)),
+        )
+            .chain()
+            .after(detect_low_pressure),
+    );
+}
+
+/// Tire pressure sensors, warming up with the `Speed` and losing air through leaks
+pub fn tpms_simulation_plugin(app: &mut App) {
+    app.insert_resource(Tires::with_leaks(&leaks_from_args()))
+        .add_systems(Update, (update_tires, detect_low_pressure).chain());
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TirePosition {
+    FrontLeft,
+    FrontRight,
+    RearLeft,
+    RearRight,
+}
+
+impl TirePosition {
+    pub const ALL: [TirePosition; 4] = [
+        TirePosition::FrontLeft,
+        TirePosition::FrontRight,
+        TirePosition::RearLeft,
+        TirePosition::RearRight,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            TirePosition::FrontLeft => "front-left",
+            TirePosition::FrontRight => "front-right",
+            TirePosition::RearLeft => "rear-left",
+            TirePosition::RearRight => "rear-right",
+        }
+    }
+
+    /// Position of the tire, at a corner of the car silhouette
+    fn offset(self) -> Vec2 {
+        let x = CAR_SIZE.x / 2.0;
+        let y = CAR_SIZE.y / 2.0 - 40.0;
+        match self {
+            TirePosition::FrontLeft => vec2(-x, y),
+            TirePosition::FrontRight => vec2(x, y),
+            TirePosition::RearLeft => vec2(-x, -y),
+            TirePosition::RearRight => vec2(x, -y),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Tire {
+    /// Pressure in bar at the ambient temperature
+    pub cold_pressure: f32,
+    /// Temperature in °C
+    pub temperature: f32,
+    /// Slow leak in bar per hour
+    pub leak: f32,
+}
+
+impl Tire {
+    /// Pressure in bar measured by the sensor, rising with the temperature like an ideal gas
+    pub fn pressure(&self) -> f32 {
+        let kelvin = |celsius: f32| celsius + 273.15;
+        (self.cold_pressure + ATMOSPHERIC_PRESSURE) * kelvin(self.temperature)
+            / kelvin(AMBIENT_TEMPERATURE)
+            - ATMOSPHERIC_PRESSURE
+    }
+
+    pub fn is_low(&self) -> bool {
+        self.pressure() < LOW_PRESSURE
+    }
+}
+
+#[derive(Resource, Clone, PartialEq, Debug)]
+pub struct Tires(pub [Tire; 4]);
+
+impl Tires {
+    fn with_leaks(leaks: &[(TirePosition, f32)]) -> Self {
+        let mut tires = [Tire {
+            cold_pressure: NOMINAL_PRESSURE,
+            temperature: AMBIENT_TEMPERATURE,
+            leak: 0.0,
+        }; 4];
+        for (position, leak) in leaks {
+            tires[*position as usize].leak = *leak;
+        }
+        Self(tires)
+    }
+
+    pub fn get(&self, position: TirePosition) -> &Tire {
+        &self.0[position as usize]
+    }
+}
+
+/// Slow leaks given with `--tire-leak <tire>:<bar per hour>`, like `--tire-leak rear-left:0.5`
+fn leaks_from_args() -> Vec<(TirePosition, f32)> {
+    let args: Vec<String> = std::env::args().collect();
+    args.windows(2)
+        .filter(|arg| arg[0] == "--tire-leak")
+        .filter_map(|arg| {
+            let leak = parse_leak(&arg[1]);
+            if leak.is_none() {
+                warn!("Invalid tire leak {}", arg[1]);
+            }
+            leak
+        })
+        .collect()
+}
+
+fn parse_leak(value: &str) -> Option<(TirePosition, f32)> {
+    let (name, rate) = value.split_once(':')?;
+    let position = TirePosition::ALL
+        .into_iter()
+        .find(|position| position.name() == name)?;
+    Some((position, rate.parse().ok()?))
+}
+
+fn update_tires(mut tires: ResMut<Tires>, speed: Option<Res<Speed>>, time: Res<Time>) {
+    let delta = time.delta_secs();
+    let speed = speed.map_or(0.0, |speed| speed.0.abs());
+    let target = AMBIENT_TEMPERATURE + speed * HEATING_PER_KMH;
+    for tire in &mut tires.0 {
+        tire.temperature += (target - tire.temperature) * (HEATING_RATE * delta).min(1.0);
+        tire.cold_pressure =
+            (tire.cold_pressure - tire.leak * delta * SIMULATED_HOURS_PER_SECOND).max(0.0);
+    }
+}
+
+fn detect_low_pressure(mut commands: Commands, tires: Res<Tires>, mut raised: Local<bool>) {
+    let low = tires.0.iter().any(Tire::is_low);
+    if low != *raised {
+        *raised = low;
+        commands.trigger(if low {
+            WarningEvent::Raise(Warning::TirePressure)
+        } else {
+            WarningEvent::Clear(Warning::TirePressure)
+        });
+    }
+}
+
+fn format_pressure(bar: f32, unit: SpeedUnit) -> String {
+    match unit {
+        SpeedUnit::Kmh => format!("{bar:.1} bar"),
+        SpeedUnit::Mph => format!("{:.0} psi", bar * PSI_PER_BAR),
+    }
+}
+
+fn format_temperature(celsius: f32, unit: SpeedUnit) -> String {
+    match unit {
+        SpeedUnit::Kmh => format!("{celsius:.0}°C"),
+        SpeedUnit::Mph => format!("{:.0}°F", celsius * 1.8 + 32.0),
+    }
+}
+
+#[derive(Component)]
+struct TireSprite(TirePosition);
+
+#[derive(Component)]
+struct TireReadout(TirePosition);
+
+fn setup(mut commands: Commands) {
+    commands
+        .spawn(Widget::TirePressure)
+        .with_children(|parent| {
+            parent.spawn(car_silhouette());
+            for position in TirePosition::ALL {
+                let offset = position.offset();
+                parent.spawn((
+                    Sprite::from_color(Color::WHITE, TIRE_SIZE),
+                    Transform::from_translation(offset.extend(0.1)),
+                    TireSprite(position),
+                ));
+                parent.spawn((
+                    Text2d::new(""),
+                    TextFont::from_font_size(16.0),
+                    Transform::from_xyz(offset.x * 2.4, offset.y, 0.0),
+                    Themed::Text,
+                    TireReadout(position),
+                ));
+            }
+        });
+}
+
+fn display(
+    tires: Res<Tires>,
+    unit: Option<Res<SpeedUnit>>,
+    theme: Res<DashboardTheme>,
+    mut sprites: Query<(&TireSprite, &mut Sprite)>,
+    mut readouts: Query<(&TireReadout, &mut Text2d)>,
+) {
+    let unit = unit.map_or(SpeedUnit::Kmh, |unit| *unit);
+    for (tire, mut sprite) in &mut sprites {
+        sprite.color = if tires.get(tire.0).is_low() {
+            Severity::Caution.color()
+        } else {
+            theme.palette.text
+        };
+    }
+    for (readout, mut text) in &mut readouts {
+        let tire = tires.get(readout.0);
+        text.0 = format!(
+            "{}\n{}",
+            format_pressure(tire.pressure(), unit),
+            format_temperature(tire.temperature, unit)
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{record, recorded, run_for, test_app};
+
+    fn tpms_app(leaks: &[(TirePosition, f32)], speed: f32) -> App {
+        let mut app = test_app();
+        app.add_plugins(tpms_simulation_plugin)
+            .insert_resource(Tires::with_leaks(leaks))
+            .insert_resource(Speed(speed));
+        record::<WarningEvent>(&mut app);
+        app
+    }
+
+    fn tire(app: &App, position: TirePosition) -> Tire {
+        *app.world().resource::<Tires>().get(position)
+    }
+
+    #[test]
+    fn driving_warms_tires_and_raises_pressure() {
+        let mut app = tpms_app(&[], 100.0);
+        run_for(&mut app, 60.0);
+        let tire = tire(&app, TirePosition::FrontLeft);
+        assert!(tire.temperature > 40.0);
+        assert!(tire.pressure() > NOMINAL_PRESSURE + 0.2);
+    }
+
+    #[test]
+    fn slow_leak_only_affects_its_tire() {
+        let mut app = tpms_app(&[(TirePosition::RearLeft, 0.6)], 0.0);
+        run_for(&mut app, 30.0);
+        assert!((tire(&app, TirePosition::RearLeft).pressure() - 2.2).abs() < 0.01);
+        assert!((tire(&app, TirePosition::RearRight).pressure() - NOMINAL_PRESSURE).abs() < 0.001);
+    }
+
+    #[test]
+    fn low_pressure_raises_a_warning() {
+        let mut app = tpms_app(&[(TirePosition::FrontRight, 1.2)], 0.0);
+        run_for(&mut app, 20.0);
+        assert!(recorded::<WarningEvent>(&app).is_empty());
+        run_for(&mut app, 10.0);
+        assert!(tire(&app, TirePosition::FrontRight).is_low());
+        assert_eq!(
+            recorded::<WarningEvent>(&app),
+            [WarningEvent::Raise(Warning::TirePressure)]
+        );
+    }
+
+    #[test]
+    fn pressure_in_bar_or_psi() {
+        assert_eq!(format_pressure(2.5, SpeedUnit::Kmh), "2.5 bar");
+        assert_eq!(format_pressure(2.5, SpeedUnit::Mph), "36 psi");
+        assert_eq!(format_temperature(20.0, SpeedUnit::Mph), "68°F");
+    }
+
+    #[test]
+    fn leak_arguments() {
+        assert_eq!(
+            parse_leak("rear-left:0.5"),
+            Some((TirePosition::RearLeft, 0.5))
+        );
+        assert_eq!(parse_leak("spare:0.5"), None);
+        assert_eq!(parse_leak("front-left"), None);
+    }
+}
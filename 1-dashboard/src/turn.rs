@@ -1,31 +1,59 @@
-use bevy::{color::palettes, prelude::*};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    input::{InputBackend, keyboard_backend},
+    navigation::TurnSuggestion,
+    theme::DashboardTheme,
+    widgets::{Widget, widget_enabled, widget_turned_on},
+};
 
 pub fn turn_plugin(app: &mut App) {
-    app.add_systems(Startup, setup)
-        .add_systems(Update, (react_to_input, blink))
-        .add_observer(update_turn_signal);
+    app.init_resource::<ActiveTurnSignal>()
+        .add_systems(
+            Update,
+            (
+                setup.run_if(widget_turned_on(Widget::TurnSignals)),
+                (react_to_input.run_if(keyboard_backend), blink, apply_theme)
+                    .run_if(widget_enabled(Widget::TurnSignals)),
+            )
+                .chain(),
+        )
+        .add_observer(track_turn_signal)
+        .add_observer(update_turn_signal)
+        .add_observer(follow_suggestion);
 }
 
-#[derive(Event)]
-enum TurnSignal {
+#[derive(Event, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TurnSignal {
     Left,
     Right,
     Stop,
 }
 
+/// The side currently signalled, if any, even while the turn signals widget is disabled
+#[derive(Resource, Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ActiveTurnSignal(pub Option<TurnSignal>);
+
+fn track_turn_signal(signal: On<TurnSignal>, mut active: ResMut<ActiveTurnSignal>) {
+    active.0 = match signal.event() {
+        TurnSignal::Stop => None,
+        side => Some(*side),
+    };
+}
+
 #[derive(Component)]
 struct TurnSignalIndicator;
 
-fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+fn setup(mut commands: Commands, asset_server: Res<AssetServer>, theme: Res<DashboardTheme>) {
     commands.spawn((
-        Transform::from_xyz(0.0, 300.0, 0.0),
-        Visibility::Visible,
+        Widget::TurnSignals,
         TurnSignalIndicator,
         children![
             (
                 Sprite {
                     image: asset_server.load("signals/signal_left.png"),
-                    color: palettes::tailwind::GRAY_800.into(),
+                    color: theme.palette.signal_off,
                     ..default()
                 },
                 Transform::from_xyz(-50.0, 0.0, 0.0),
@@ -33,7 +61,7 @@ fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
             (
                 Sprite {
                     image: asset_server.load("signals/signal_right.png"),
-                    color: palettes::tailwind::GRAY_800.into(),
+                    color: theme.palette.signal_off,
                     ..default()
                 },
                 Transform::from_xyz(50.0, 0.0, 0.0),
@@ -57,9 +85,10 @@ fn update_turn_signal(
     indicator: Single<(Entity, &Children), With<TurnSignalIndicator>>,
     mut commands: Commands,
     mut sprites: Query<&mut Sprite>,
+    theme: Res<DashboardTheme>,
 ) {
-    sprites.get_mut(indicator.1[0]).unwrap().color = palettes::tailwind::GRAY_800.into();
-    sprites.get_mut(indicator.1[1]).unwrap().color = palettes::tailwind::GRAY_800.into();
+    sprites.get_mut(indicator.1[0]).unwrap().color = theme.palette.signal_off;
+    sprites.get_mut(indicator.1[1]).unwrap().color = theme.palette.signal_off;
 
     match signal.event() {
         TurnSignal::Left => {
@@ -78,10 +107,39 @@ fn update_turn_signal(
     }
 }
 
+// Signal ahead of a suggested turn unless already signalling, and cancel the signal once the turn is taken
+fn follow_suggestion(
+    suggestion: On<TurnSuggestion>,
+    indicator: Single<(&Children, Option<&Blink>), With<TurnSignalIndicator>>,
+    backend: Res<InputBackend>,
+    mut commands: Commands,
+) {
+    // Signals from the vehicle are not overridden
+    if *backend != InputBackend::Keyboard {
+        return;
+    }
+    let (children, blink) = *indicator;
+    match (suggestion.event(), blink) {
+        (TurnSuggestion::Upcoming(signal), None) => commands.trigger(*signal),
+        (TurnSuggestion::Completed(signal), Some(blink)) => {
+            let side = match signal {
+                TurnSignal::Left => children[0],
+                TurnSignal::Right => children[1],
+                TurnSignal::Stop => return,
+            };
+            if blink.target == side {
+                commands.trigger(TurnSignal::Stop);
+            }
+        }
+        _ => (),
+    }
+}
+
 #[derive(Component)]
 struct Blink {
     target: Entity,
     timer: Timer,
+    lit: bool,
 }
 
 impl Blink {
@@ -89,17 +147,114 @@ impl Blink {
         Self {
             target: entity,
             timer: Timer::from_seconds(0.5, TimerMode::Repeating),
+            lit: false,
         }
     }
 }
 
-fn blink(mut blink: Single<&mut Blink>, mut sprites: Query<&mut Sprite>, time: Res<Time>) {
+fn blink(
+    mut blink: Single<&mut Blink>,
+    mut sprites: Query<&mut Sprite>,
+    time: Res<Time>,
+    theme: Res<DashboardTheme>,
+) {
     if blink.timer.tick(time.delta()).just_finished() {
-        let mut sprite = sprites.get_mut(blink.target).unwrap();
-        sprite.color = if sprite.color == Color::WHITE {
-            palettes::tailwind::GRAY_800.into()
+        blink.lit = !blink.lit;
+        sprites.get_mut(blink.target).unwrap().color = if blink.lit {
+            theme.palette.signal_on
         } else {
-            Color::WHITE
+            theme.palette.signal_off
         };
     }
 }
+
+fn apply_theme(
+    theme: Res<DashboardTheme>,
+    indicator: Single<(&Children, Option<&Blink>), With<TurnSignalIndicator>>,
+    mut sprites: Query<&mut Sprite>,
+) {
+    if theme.is_changed() {
+        let (children, blink) = *indicator;
+        for child in children {
+            let lit = blink.is_some_and(|blink| blink.target == *child && blink.lit);
+            sprites.get_mut(*child).unwrap().color = if lit {
+                theme.palette.signal_on
+            } else {
+                theme.palette.signal_off
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        testing::{record, recorded, test_app},
+        theme::theme_plugin,
+    };
+
+    // The turn signal observers, with an indicator but no widget systems
+    fn turn_app() -> App {
+        let mut app = test_app();
+        app.add_plugins(theme_plugin)
+            .init_resource::<ClearColor>()
+            .init_resource::<ActiveTurnSignal>()
+            .add_observer(track_turn_signal)
+            .add_observer(update_turn_signal)
+            .add_observer(follow_suggestion);
+        record::<TurnSignal>(&mut app);
+        app.world_mut().spawn((
+            TurnSignalIndicator,
+            children![Sprite::default(), Sprite::default()],
+        ));
+        app
+    }
+
+    fn trigger(app: &mut App, event: impl Event<Trigger<'static>: Default>) {
+        app.world_mut().trigger(event);
+        app.update();
+    }
+
+    fn active(app: &App) -> Option<TurnSignal> {
+        app.world().resource::<ActiveTurnSignal>().0
+    }
+
+    #[test]
+    fn active_signal_tracks_the_events() {
+        let mut app = turn_app();
+        assert_eq!(active(&app), None);
+        trigger(&mut app, TurnSignal::Left);
+        assert_eq!(active(&app), Some(TurnSignal::Left));
+        trigger(&mut app, TurnSignal::Right);
+        assert_eq!(active(&app), Some(TurnSignal::Right));
+        trigger(&mut app, TurnSignal::Stop);
+        assert_eq!(active(&app), None);
+    }
+
+    #[test]
+    fn suggestions_start_and_cancel_the_signal() {
+        let mut app = turn_app();
+        trigger(&mut app, TurnSuggestion::Upcoming(TurnSignal::Left));
+        assert_eq!(active(&app), Some(TurnSignal::Left));
+
+        // The driver's signal is kept, and only cancelled by its own turn
+        trigger(&mut app, TurnSuggestion::Upcoming(TurnSignal::Right));
+        trigger(&mut app, TurnSuggestion::Completed(TurnSignal::Right));
+        assert_eq!(active(&app), Some(TurnSignal::Left));
+        trigger(&mut app, TurnSuggestion::Completed(TurnSignal::Left));
+        assert_eq!(active(&app), None);
+        assert_eq!(
+            recorded::<TurnSignal>(&app),
+            [TurnSignal::Left, TurnSignal::Stop]
+        );
+    }
+
+    #[test]
+    fn suggestions_leave_vehicle_signals_alone() {
+        let mut app = turn_app();
+        app.insert_resource(InputBackend::Can);
+        trigger(&mut app, TurnSuggestion::Upcoming(TurnSignal::Right));
+        assert_eq!(active(&app), None);
+    }
+}
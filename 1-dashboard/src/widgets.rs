@@ -0,0 +1,179 @@
+use bevy::{platform::collections::HashSet, prelude::*};
+use serde::Deserialize;
+
+use crate::theme::Themed;
+
+pub fn widgets_plugin(app: &mut App) {
+    app.add_systems(Update, (toggle_widgets, despawn_disabled_widgets).chain());
+}
+
+/// Root of a dashboard widget
+#[derive(Component, Deserialize, Clone, Copy, Hash, PartialEq, Eq, Debug)]
+#[require(Transform, Visibility::Hidden)]
+pub enum Widget {
+    Speedometer,
+    Odometer,
+    Battery,
+    Radio,
+    TurnSignals,
+    Telltales,
+    SpeedLimit,
+    Cruise,
+    Gear,
+    Charging,
+    PowerMeter,
+    Navigation,
+    Notifications,
+    Climate,
+    TirePressure,
+    Doors,
+    TripSummary,
+}
+
+impl Widget {
+    const ALL: [Widget; 17] = [
+        Widget::Speedometer,
+        Widget::Odometer,
+        Widget::Battery,
+        Widget::Radio,
+        Widget::TurnSignals,
+        Widget::Telltales,
+        Widget::SpeedLimit,
+        Widget::Cruise,
+        Widget::Gear,
+        Widget::Charging,
+        Widget::PowerMeter,
+        Widget::Navigation,
+        Widget::Notifications,
+        Widget::Climate,
+        Widget::TirePressure,
+        Widget::Doors,
+        Widget::TripSummary,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            Widget::Speedometer => "speedometer",
+            Widget::Odometer => "odometer",
+            Widget::Battery => "battery",
+            Widget::Radio => "radio",
+            Widget::TurnSignals => "turn-signals",
+            Widget::Telltales => "telltales",
+            Widget::SpeedLimit => "speed-limit",
+            Widget::Cruise => "cruise",
+            Widget::Gear => "gear",
+            Widget::Charging => "charging",
+            Widget::PowerMeter => "power-meter",
+            Widget::Navigation => "navigation",
+            Widget::Notifications => "notifications",
+            Widget::Climate => "climate",
+            Widget::TirePressure => "tire-pressure",
+            Widget::Doors => "doors",
+            Widget::TripSummary => "trip-summary",
+        }
+    }
+
+    /// Widgets without a key can still be disabled with `--no-<widget>`
+    fn toggle_key(self) -> Option<KeyCode> {
+        match self {
+            Widget::Speedometer => Some(KeyCode::F1),
+            Widget::Odometer => Some(KeyCode::F2),
+            Widget::Battery => Some(KeyCode::F3),
+            Widget::Radio => Some(KeyCode::F4),
+            Widget::TurnSignals => Some(KeyCode::F5),
+            Widget::Telltales => Some(KeyCode::F6),
+            Widget::SpeedLimit => Some(KeyCode::F7),
+            Widget::Cruise => Some(KeyCode::F8),
+            Widget::Gear => Some(KeyCode::F9),
+            Widget::Charging => Some(KeyCode::F10),
+            Widget::PowerMeter => Some(KeyCode::F11),
+            Widget::Navigation => Some(KeyCode::F12),
+            Widget::Notifications
+            | Widget::Climate
+            | Widget::TirePressure
+            | Widget::Doors
+            | Widget::TripSummary => None,
+        }
+    }
+}
+
+#[derive(Resource, Clone)]
+pub struct EnabledWidgets(HashSet<Widget>);
+
+impl Default for EnabledWidgets {
+    fn default() -> Self {
+        Self(Widget::ALL.into_iter().collect())
+    }
+}
+
+impl EnabledWidgets {
+    /// All widgets are enabled, except those disabled with a `--no-<widget>` flag
+    pub fn from_args() -> Self {
+        let args: Vec<String> = std::env::args().collect();
+        let mut enabled = Self::default();
+        for widget in Widget::ALL {
+            if args.contains(&format!("--no-{}", widget.name())) {
+                enabled.0.remove(&widget);
+            }
+        }
+        enabled
+    }
+
+    pub fn contains(&self, widget: Widget) -> bool {
+        self.0.contains(&widget)
+    }
+}
+
+/// Size of the car silhouette, in pixels
+pub const CAR_SIZE: Vec2 = vec2(80.0, 180.0);
+
+/// Car seen from above with the front at the top, drawn by the widgets showing its doors or tires
+pub fn car_silhouette() -> impl Bundle {
+    (
+        Sprite::from_color(Color::WHITE, CAR_SIZE),
+        Themed::SecondaryText,
+    )
+}
+
+/// Run condition: the widget is currently enabled
+pub fn widget_enabled(widget: Widget) -> impl FnMut(Res<EnabledWidgets>) -> bool + Clone {
+    move |enabled: Res<EnabledWidgets>| enabled.contains(widget)
+}
+
+/// Run condition: the widget was enabled since the last time this condition ran
+pub fn widget_turned_on(
+    widget: Widget,
+) -> impl FnMut(Res<EnabledWidgets>, Local<bool>) -> bool + Clone {
+    move |enabled: Res<EnabledWidgets>, mut was_enabled: Local<bool>| {
+        let is_enabled = enabled.contains(widget);
+        let turned_on = is_enabled && !*was_enabled;
+        *was_enabled = is_enabled;
+        turned_on
+    }
+}
+
+fn toggle_widgets(input: Res<ButtonInput<KeyCode>>, mut enabled: ResMut<EnabledWidgets>) {
+    for widget in Widget::ALL {
+        if widget
+            .toggle_key()
+            .is_some_and(|key| input.just_pressed(key))
+            && !enabled.0.remove(&widget)
+        {
+            enabled.0.insert(widget);
+        }
+    }
+}
+
+fn despawn_disabled_widgets(
+    mut commands: Commands,
+    enabled: Res<EnabledWidgets>,
+    widgets: Query<(Entity, &Widget)>,
+) {
+    if enabled.is_changed() {
+        for (entity, widget) in &widgets {
+            if !enabled.contains(*widget) {
+                commands.entity(entity).despawn();
+            }
+        }
+    }
+}